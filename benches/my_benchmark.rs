@@ -1,132 +1,204 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::time::Duration;
-use wgpu_test::tropical_matmul::{BlockedTropicalMatmulKernel, NaiveTropicalMatmulKernel};
+use wgpu_test::{
+    bfs::Bfs,
+    cpu, generators,
+    graph::Graph,
+    tropical_matmul::{
+        BlockedTropicalMatmulKernel, NaiveTropicalMatmulKernel, RegisterTiledTropicalMatmulKernel,
+        TropicalMatmul, TropicalMatmulKernel,
+    },
+    warshall_floyd::WarshallFloyd,
+    workspace::Workspace,
+};
 
-fn warshall_floyd_cpu(n: usize, edges: &[(usize, usize)], distance: &mut [f32]) {
-    distance.fill(std::f32::INFINITY);
-    for i in 0..n {
-        distance[i * n + i] = 0.;
+const SIZES: [usize; 4] = [256, 512, 1024, 2048];
+const CPU_MAX_N: usize = 512;
+
+type Generator = fn(usize) -> Graph;
+
+// Graph families with an average degree of about 8. `n` is a power of two.
+fn families() -> Vec<(&'static str, Generator)> {
+    vec![
+        ("path", |n| {
+            Graph::new(n, (1..n).map(|i| (i - 1, i)).collect())
+        }),
+        ("erdos-renyi", |n| {
+            generators::erdos_renyi(n, 8. / n as f64, 0)
+        }),
+        ("barabasi-albert", |n| generators::barabasi_albert(n, 4, 0)),
+        ("grid", |n| {
+            let rows = 1 << (n.ilog2() / 2);
+            generators::grid(rows, n / rows)
+        }),
+        ("random-geometric", |n| {
+            let radius = (8. / (std::f32::consts::PI * n as f32)).sqrt();
+            generators::random_geometric(n, radius, 0)
+        }),
+        ("watts-strogatz", |n| {
+            generators::watts_strogatz(n, 8, 0.1, 0)
+        }),
+    ]
+}
+
+trait Apsp {
+    fn create_workspace(&self, device: &wgpu::Device, n: usize) -> Workspace;
+    fn stage_graph(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        graph: &Graph,
+        dst: &wgpu::Buffer,
+    );
+    fn run_workspace(&self, device: &wgpu::Device, queue: &wgpu::Queue, workspace: &Workspace);
+}
+
+impl Apsp for WarshallFloyd<f32> {
+    fn create_workspace(&self, device: &wgpu::Device, n: usize) -> Workspace {
+        self.create_workspace(device, n)
     }
-    for &(i, j) in edges.iter() {
-        distance[i * n + j] = 1.;
-        distance[j * n + i] = 1.;
+
+    fn stage_graph(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        graph: &Graph,
+        dst: &wgpu::Buffer,
+    ) {
+        self.stage_graph(device, queue, graph, dst)
     }
-    for k in 0..n {
-        for i in 0..n {
-            for j in 0..n {
-                let d = distance[i * n + k] + distance[k * n + j];
-                if d < distance[i * n + j] {
-                    distance[i * n + j] = d;
-                }
-            }
-        }
+
+    fn run_workspace(&self, device: &wgpu::Device, queue: &wgpu::Queue, workspace: &Workspace) {
+        self.run_workspace(device, queue, workspace)
     }
 }
 
-fn create_graph(n: usize) -> Vec<(usize, usize)> {
-    (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>()
+impl<K: TropicalMatmulKernel<Element = f32>> Apsp for TropicalMatmul<K> {
+    fn create_workspace(&self, device: &wgpu::Device, n: usize) -> Workspace {
+        self.create_workspace(device, n)
+    }
+
+    fn stage_graph(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        graph: &Graph,
+        dst: &wgpu::Buffer,
+    ) {
+        self.stage_graph(device, queue, graph, dst)
+    }
+
+    fn run_workspace(&self, device: &wgpu::Device, queue: &wgpu::Queue, workspace: &Workspace) {
+        self.run_workspace(device, queue, workspace)
+    }
 }
 
+// Throughput is reported in elements per second, each element being one of
+// the n³ relaxations of Warshall-Floyd, so the tropical kernels are compared
+// against the same amount of work even though they do more. BFS does O(n·m)
+// work and is reported separately, with n·m elements.
 fn criterion_benchmark(c: &mut Criterion) {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(async {
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::downlevel_defaults(),
-                },
-                None,
-            )
-            .await
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
             .unwrap();
-        let wf = wgpu_test::warshall_floyd::WarshallFloyd::new(&device);
-        let tm_naive = wgpu_test::tropical_matmul::TropicalMatmul::new(
-            NaiveTropicalMatmulKernel::new(&device),
-        );
-        let tm_block = wgpu_test::tropical_matmul::TropicalMatmul::new(
-            BlockedTropicalMatmulKernel::new(&device),
-        );
-        {
-            let mut group = c.benchmark_group("APSP");
-            for n in (128..=1024).step_by(128) {
-                let graph = create_graph(n);
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            features: wgpu::Features::empty(),
+            limits: adapter.limits(),
+        },
+        None,
+    ))
+    .unwrap();
+    let max_n = WarshallFloyd::<f32>::max_n(&device.limits());
+    let kernels: Vec<(&str, Box<dyn Apsp>)> = vec![
+        (
+            "warshall-floyd",
+            Box::new(WarshallFloyd::<f32>::new(&device)),
+        ),
+        (
+            "tropical-matmul",
+            Box::new(TropicalMatmul::new(NaiveTropicalMatmulKernel::<f32>::new(
+                &device,
+            ))),
+        ),
+        (
+            "tropical-matmul-block",
+            Box::new(TropicalMatmul::new(
+                BlockedTropicalMatmulKernel::<f32>::new(&device),
+            )),
+        ),
+        (
+            "tropical-matmul-register",
+            Box::new(TropicalMatmul::new(RegisterTiledTropicalMatmulKernel::new(
+                &device,
+            ))),
+        ),
+    ];
+    let bfs = Bfs::new(&device);
 
-                group.bench_with_input(
-                    BenchmarkId::new("CPU warshall-floyd", n),
-                    &graph,
-                    |bench, graph| {
-                        let mut distance = vec![0.; n * n];
-                        bench.iter(|| warshall_floyd_cpu(n, &graph, &mut distance));
-                    },
-                );
+    for (family, generate) in families() {
+        let graphs = SIZES
+            .into_iter()
+            .filter(|&n| n <= max_n)
+            .map(|n| (n, generate(n)))
+            .collect::<Vec<_>>();
+        let mut group = c.benchmark_group(format!("APSP/{}", family));
+        for &(n, ref graph) in &graphs {
+            group.throughput(Throughput::Elements((n * n * n) as u64));
 
+            if n <= CPU_MAX_N {
                 group.bench_with_input(
-                    BenchmarkId::new("GPU warshall-floyd", n),
-                    &graph,
-                    |bench, graph| {
-                        let size = wgpu_test::warshall_floyd::WarshallFloydKernel::buffer_size(n);
-                        let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                            label: None,
-                            size: size as u64,
-                            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-                            mapped_at_creation: false,
-                        });
-                        let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                            label: None,
-                            size: size as u64,
-                            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-                            mapped_at_creation: false,
-                        });
-                        bench.iter(|| {
-                            wgpu_test::warshall_floyd::stage_adjacency_matrix(
-                                &device, &queue, &graph, n, &in_buffer,
-                            );
-                            wf.run(&device, &queue, &in_buffer, &out_buffer, n);
-                            device.poll(wgpu::Maintain::Wait);
-                        });
-                    },
+                    BenchmarkId::new("CPU warshall-floyd", n),
+                    graph,
+                    |bench, graph| bench.iter(|| cpu::warshall_floyd_graph::<f32>(graph)),
                 );
+            }
 
+            for (name, kernel) in &kernels {
                 group.bench_with_input(
-                    BenchmarkId::new("GPU tropical-matmul", n),
-                    &graph,
+                    BenchmarkId::new(format!("GPU {}", name), n),
+                    graph,
                     |bench, graph| {
-                        let (in_buffer, out_buffer) = tm_naive.create_buffer(&device, n);
+                        let workspace = kernel.create_workspace(&device, n);
                         bench.iter(|| {
-                            tm_naive.stage_adjacency_matrix(&device, &queue, &graph, n, &in_buffer);
-                            tm_naive.run(&device, &queue, &in_buffer, &out_buffer, n);
+                            kernel.stage_graph(&device, &queue, graph, workspace.in_buffer());
+                            kernel.run_workspace(&device, &queue, &workspace);
                             device.poll(wgpu::Maintain::Wait);
                         });
                     },
                 );
+            }
+        }
+        group.finish();
 
-                group.bench_with_input(
-                    BenchmarkId::new("GPU tropical-matmul-block", n),
-                    &graph,
-                    |bench, graph| {
-                        let (in_buffer, out_buffer) = tm_block.create_buffer(&device, n);
-                        bench.iter(|| {
-                            tm_block.stage_adjacency_matrix(&device, &queue, &graph, n, &in_buffer);
-                            tm_block.run(&device, &queue, &in_buffer, &out_buffer, n);
-                            device.poll(wgpu::Maintain::Wait);
-                        });
-                    },
-                );
+        if graphs.iter().any(|(_, graph)| graph.weights.is_some()) {
+            continue;
+        }
+        let mut group = c.benchmark_group(format!("BFS/{}", family));
+        for &(n, ref graph) in &graphs {
+            if n > Bfs::max_n(&device.limits()) {
+                continue;
             }
+            group.throughput(Throughput::Elements((n * graph.edges.len()) as u64));
+            group.bench_with_input(BenchmarkId::new("GPU bfs", n), graph, |bench, graph| {
+                let buffer = bfs.create_buffer(&device, n);
+                bench.iter(|| {
+                    pollster::block_on(bfs.run(&device, &queue, &graph.edges, n, &buffer));
+                    device.poll(wgpu::Maintain::Wait);
+                });
+            });
         }
-    })
+        group.finish();
+    }
 }
 
 criterion_group! {
     name = benches;
-    config = Criterion::default().measurement_time(Duration::new(5, 0));
+    config = Criterion::default()
+        .sample_size(10)
+        .measurement_time(Duration::new(5, 0));
     targets = criterion_benchmark
 }
 criterion_main!(benches);
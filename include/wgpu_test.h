@@ -0,0 +1,63 @@
+#ifndef WGPU_TEST_H
+#define WGPU_TEST_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum ApspStatus {
+  APSP_STATUS_OK = 0,
+  APSP_STATUS_INVALID_ARGUMENT = 1,
+  APSP_STATUS_NO_ADAPTER = 2,
+  APSP_STATUS_DEVICE_ERROR = 3,
+  APSP_STATUS_TOO_LARGE = 4,
+  APSP_STATUS_NOT_STAGED = 5,
+  APSP_STATUS_NOT_RUN = 6,
+  APSP_STATUS_BUFFER_TOO_SMALL = 7,
+  APSP_STATUS_INTERNAL = 8,
+} ApspStatus;
+
+typedef enum ApspAlgorithm {
+  APSP_ALGORITHM_WARSHALL_FLOYD = 0,
+  APSP_ALGORITHM_TROPICAL_NAIVE = 1,
+  APSP_ALGORITHM_TROPICAL_BLOCK = 2,
+  APSP_ALGORITHM_BFS = 3,
+  APSP_ALGORITHM_CPU = 4,
+} ApspAlgorithm;
+
+typedef struct ApspContext ApspContext;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+enum ApspStatus apsp_context_create(struct ApspContext **context);
+
+void apsp_context_destroy(struct ApspContext *context);
+
+enum ApspStatus apsp_stage_edges(struct ApspContext *context,
+                                 size_t n,
+                                 const uint32_t *edges,
+                                 const float *weights,
+                                 size_t m,
+                                 bool directed);
+
+enum ApspStatus apsp_run(struct ApspContext *context, uint32_t algorithm);
+
+size_t apsp_vertex_count(const struct ApspContext *context);
+
+enum ApspStatus apsp_copy_distances(struct ApspContext *context, float *dst, size_t len);
+
+const char *apsp_last_error(const struct ApspContext *context);
+
+const char *apsp_status_string(uint32_t status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WGPU_TEST_H */
@@ -0,0 +1,74 @@
+# Run with `pip install -e .[test] && pytest python/tests`. Set
+# WGPU_BACKEND=gl to use llvmpipe where there is no hardware adapter.
+import numpy as np
+import pytest
+import scipy.sparse
+from scipy.sparse.csgraph import shortest_path
+
+import wgpu_test
+
+
+def random_graph(n, m, seed=0):
+    rng = np.random.default_rng(seed)
+    edges = rng.integers(0, n, size=(m, 2))
+    weights = rng.uniform(1, 10, size=m).astype(np.float32)
+    return edges, weights
+
+
+@pytest.mark.parametrize("apsp", [wgpu_test.floyd_warshall, wgpu_test.tropical_apsp])
+@pytest.mark.parametrize("directed", [True, False])
+def test_matches_scipy(apsp, directed):
+    n = 50
+    edges, weights = random_graph(n, 200)
+    edges = edges[edges[:, 0] != edges[:, 1]]
+    weights = weights[: len(edges)]
+    # Keeps the lightest of parallel edges, like the crate does.
+    dense = np.full((n, n), np.inf, dtype=np.float32)
+    np.minimum.at(dense, (edges[:, 0], edges[:, 1]), weights)
+    expected = shortest_path(np.where(np.isinf(dense), 0, dense), directed=directed)
+
+    actual = apsp(edges, n=n, weights=weights, directed=directed)
+    assert actual.dtype == np.float32
+    assert actual.shape == (n, n)
+    np.testing.assert_allclose(actual, expected, rtol=1e-5)
+
+    sparse = scipy.sparse.csr_matrix(np.where(np.isinf(dense), 0, dense))
+    np.testing.assert_allclose(apsp(sparse, directed=directed), expected, rtol=1e-5)
+
+
+def test_unweighted_edges():
+    distance = wgpu_test.floyd_warshall([[0, 1], [1, 2]], directed=False)
+    np.testing.assert_array_equal(distance, [[0, 1, 2], [1, 0, 1], [2, 1, 0]])
+
+
+def test_unreachable():
+    distance = wgpu_test.floyd_warshall(np.array([[0, 1]]), n=3)
+    assert distance[0, 1] == 1
+    assert np.isinf(distance[1, 0])
+    assert np.isinf(distance[0, 2])
+
+
+def test_invalid_input():
+    with pytest.raises(ValueError):
+        wgpu_test.floyd_warshall([[0, 5]], n=3)
+    with pytest.raises(ValueError):
+        wgpu_test.floyd_warshall([[0, 1]], weights=[1.0, 2.0])
+    with pytest.raises(ValueError):
+        wgpu_test.floyd_warshall(scipy.sparse.csr_matrix((2, 3)))
+
+
+def test_metrics():
+    n = 10
+    edges = np.array([(i - 1, i) for i in range(1, n)])
+    metrics = wgpu_test.metrics(edges, directed=False)
+    np.testing.assert_array_equal(
+        metrics["eccentricity"], [max(i, n - 1 - i) for i in range(n)]
+    )
+    assert metrics["diameter"] == 9
+    assert metrics["radius"] == 5
+    np.testing.assert_array_equal(metrics["center"], [4, 5])
+    np.testing.assert_array_equal(metrics["periphery"], [0, 9])
+    assert metrics["wiener_index"] == 165
+    assert metrics["average_shortest_path_length"] == pytest.approx(11 / 3)
+    assert metrics["closeness"].shape == (n,)
+    assert metrics["harmonic"].shape == (n,)
@@ -0,0 +1,172 @@
+// One entry point for the GPU algorithms, shared by the command line tool and
+// the language bindings, which only map `ApspError` to their own errors.
+use crate::{
+    bfs::Bfs,
+    graph::Graph,
+    tropical_matmul::{
+        BlockedTropicalMatmulKernel, NaiveTropicalMatmulKernel, TropicalMatmul,
+        TropicalMatmulKernel,
+    },
+    warshall_floyd::{WarshallFloyd, WarshallFloydKernel},
+};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    WarshallFloyd,
+    TropicalNaive,
+    TropicalBlock,
+    // Undirected, unweighted graphs only.
+    Bfs,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApspError {
+    Empty,
+    // The padded matrix of the algorithm does not fit in a storage binding.
+    TooLarge { n: usize, max_n: usize },
+    Unsupported(Algorithm),
+}
+
+impl fmt::Display for ApspError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApspError::Empty => write!(f, "graph has no vertices"),
+            ApspError::TooLarge { n, max_n } => write!(
+                f,
+                "graph has {} vertices, the device supports at most {}",
+                n, max_n
+            ),
+            ApspError::Unsupported(Algorithm::Bfs) => {
+                write!(f, "bfs only supports undirected, unweighted graphs")
+            }
+            ApspError::Unsupported(algorithm) => {
+                write!(f, "{:?} does not support the graph", algorithm)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApspError {}
+
+fn check_size(n: usize, max_n: usize) -> Result<(), ApspError> {
+    if n > max_n {
+        return Err(ApspError::TooLarge { n, max_n });
+    }
+    Ok(())
+}
+
+// Stages `graph`, runs `algorithm` and returns the buffer holding the distance
+// matrix and its stride.
+pub async fn run(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    graph: &Graph,
+    algorithm: Algorithm,
+) -> Result<(wgpu::Buffer, usize), ApspError> {
+    let n = graph.n;
+    if n == 0 {
+        return Err(ApspError::Empty);
+    }
+    match algorithm {
+        Algorithm::WarshallFloyd => {
+            check_size(n, WarshallFloyd::<f32>::max_n(&device.limits()))?;
+            let wf = WarshallFloyd::<f32>::new(device);
+            let (in_buffer, out_buffer) = wf.create_buffer(device, n);
+            wf.stage_graph(device, queue, graph, &in_buffer);
+            wf.run(device, queue, &in_buffer, &out_buffer, n);
+            Ok((out_buffer, WarshallFloydKernel::stride_x(n)))
+        }
+        Algorithm::TropicalNaive => run_tropical(
+            device,
+            queue,
+            NaiveTropicalMatmulKernel::<f32>::new(device),
+            graph,
+        ),
+        Algorithm::TropicalBlock => run_tropical(
+            device,
+            queue,
+            BlockedTropicalMatmulKernel::<f32>::new(device),
+            graph,
+        ),
+        Algorithm::Bfs => {
+            if graph.directed || graph.weights.is_some() {
+                return Err(ApspError::Unsupported(algorithm));
+            }
+            check_size(n, Bfs::max_n(&device.limits()))?;
+            let bfs = Bfs::new(device);
+            let buffer = bfs.create_buffer(device, n);
+            bfs.run(device, queue, &graph.edges, n, &buffer).await;
+            Ok((buffer, Bfs::stride(n)))
+        }
+    }
+}
+
+fn run_tropical<K: TropicalMatmulKernel<Element = f32>>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    kernel: K,
+    graph: &Graph,
+) -> Result<(wgpu::Buffer, usize), ApspError> {
+    let n = graph.n;
+    let tm = TropicalMatmul::new(kernel);
+    check_size(n, tm.max_n(&device.limits()))?;
+    let (in_buffer, out_buffer) = tm.create_buffer(device, n);
+    tm.stage_graph(device, queue, graph, &in_buffer);
+    tm.run(device, queue, &in_buffer, &out_buffer, n);
+    Ok((out_buffer, tm.stride(n)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu, matrix::DistanceMatrix, testing::init};
+
+    #[tokio::test]
+    async fn test_run() {
+        let (device, queue) = init().await;
+        let graph = Graph::new(5, vec![(0, 1), (1, 2), (2, 3)]);
+        let expected = cpu::warshall_floyd_graph::<f32>(&graph);
+        for algorithm in [
+            Algorithm::WarshallFloyd,
+            Algorithm::TropicalNaive,
+            Algorithm::TropicalBlock,
+            Algorithm::Bfs,
+        ] {
+            let (buffer, stride) = run(&device, &queue, &graph, algorithm).await.unwrap();
+            let distance = DistanceMatrix::<f32>::download(&device, &queue, &buffer, 5, stride)
+                .await
+                .unwrap();
+            assert_eq!(distance.as_slice(), expected.as_slice(), "{:?}", algorithm);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_errors() {
+        let (device, queue) = init().await;
+        let error = |graph, algorithm| {
+            let (device, queue) = (&device, &queue);
+            async move { run(device, queue, &graph, algorithm).await.unwrap_err() }
+        };
+        assert_eq!(
+            error(Graph::default(), Algorithm::WarshallFloyd).await,
+            ApspError::Empty
+        );
+        let directed = Graph {
+            directed: true,
+            ..Graph::new(2, vec![(0, 1)])
+        };
+        assert_eq!(
+            error(directed, Algorithm::Bfs).await,
+            ApspError::Unsupported(Algorithm::Bfs)
+        );
+        let max_n = WarshallFloyd::<f32>::max_n(&device.limits());
+        assert_eq!(
+            error(Graph::new(max_n + 1, vec![]), Algorithm::WarshallFloyd).await,
+            ApspError::TooLarge {
+                n: max_n + 1,
+                max_n
+            }
+        );
+    }
+}
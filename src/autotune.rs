@@ -0,0 +1,298 @@
+use crate::{
+    distance::Distance,
+    tropical_matmul::{
+        BlockedTropicalMatmulKernel, RegisterTiledTropicalMatmulKernel, TropicalMatmul,
+        TropicalMatmulKernel,
+    },
+    warshall_floyd::WarshallFloyd,
+};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+// Picks kernel configurations by timing the candidates on the current
+// adapter. Results are kept in a tab separated file, one line per adapter,
+// kernel and problem size, and reused instead of measuring again.
+pub struct Autotuner {
+    path: PathBuf,
+    adapter: String,
+    cache: BTreeMap<String, Vec<usize>>,
+}
+
+impl Autotuner {
+    pub fn new(path: impl Into<PathBuf>, info: &wgpu::AdapterInfo) -> io::Result<Autotuner> {
+        let path = path.into();
+        let mut cache = BTreeMap::new();
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                for line in content.lines() {
+                    if let Some((key, value)) = line.split_once('\t') {
+                        if let Ok(config) = value.split(',').map(str::parse).collect() {
+                            cache.insert(key.to_string(), config);
+                        }
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let adapter = format!(
+            "{} {:?} {:04x}:{:04x}",
+            info.name, info.backend, info.vendor, info.device
+        )
+        .replace(['\t', '\n'], " ");
+        Ok(Autotuner {
+            path,
+            adapter,
+            cache,
+        })
+    }
+
+    pub fn cached(&self, name: &str, n: usize) -> Option<&[usize]> {
+        self.cache.get(&self.key(name, n)).map(Vec::as_slice)
+    }
+
+    // Returns the candidate with the smallest `measure` time, or the cached
+    // choice if this kernel was already tuned for `n` on this adapter. Fails
+    // with `InvalidInput` if there are no candidates.
+    pub fn tune<F: FnMut(&[usize]) -> Duration>(
+        &mut self,
+        name: &str,
+        n: usize,
+        candidates: &[Vec<usize>],
+        mut measure: F,
+    ) -> io::Result<Vec<usize>> {
+        if let Some(config) = self.cached(name, n) {
+            return Ok(config.to_vec());
+        }
+        let best = candidates
+            .iter()
+            .map(|config| (measure(config), config))
+            .min_by_key(|&(time, _)| time)
+            .map(|(_, config)| config.clone())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no candidate configurations")
+            })?;
+        self.cache.insert(self.key(name, n), best.clone());
+        self.save()?;
+        Ok(best)
+    }
+
+    pub fn warshall_floyd<T: Distance>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        n: usize,
+    ) -> io::Result<WarshallFloyd<T>> {
+        let limits = device.limits();
+        let candidates = [[8, 8], [16, 8], [16, 16], [32, 8], [64, 4]]
+            .iter()
+            .filter(|&&[x, y]| {
+                x * y <= limits.max_compute_invocations_per_workgroup as usize
+                    && x <= limits.max_compute_workgroup_size_x as usize
+                    && y <= limits.max_compute_workgroup_size_y as usize
+            })
+            .map(|c| c.to_vec())
+            .collect::<Vec<_>>();
+        let edges = tuning_graph(n);
+        let config = self.tune(
+            &format!("warshall_floyd<{}>", std::any::type_name::<T>()),
+            n,
+            &candidates,
+            |config| {
+                let wf = WarshallFloyd::<T>::with_workgroup_size(device, config[0], config[1]);
+                let (in_buffer, out_buffer) = wf.create_buffer(device, n);
+                wf.stage_adjacency_matrix(device, queue, &edges, n, &in_buffer);
+                time(device, || {
+                    wf.run(device, queue, &in_buffer, &out_buffer, n);
+                })
+            },
+        )?;
+        Ok(WarshallFloyd::with_workgroup_size(
+            device, config[0], config[1],
+        ))
+    }
+
+    pub fn blocked_tropical_matmul<T: Distance>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        n: usize,
+    ) -> io::Result<BlockedTropicalMatmulKernel<T>> {
+        let limits = device.limits();
+        let candidates = [4, 8, 16]
+            .into_iter()
+            .filter(|&t| {
+                // One tile of scalars and one of unpacked words, at most
+                // 8 bytes per element.
+                t * t <= limits.max_compute_invocations_per_workgroup as usize
+                    && 2 * t * t * 8 <= limits.max_compute_workgroup_storage_size as usize
+            })
+            .map(|t| vec![t])
+            .collect::<Vec<_>>();
+        let config = self.tune(
+            &format!("blocked_tropical_matmul<{}>", std::any::type_name::<T>()),
+            n,
+            &candidates,
+            |config| {
+                measure_tropical_matmul(
+                    device,
+                    queue,
+                    BlockedTropicalMatmulKernel::<T>::with_tile_size(device, config[0]),
+                    n,
+                )
+            },
+        )?;
+        Ok(BlockedTropicalMatmulKernel::with_tile_size(
+            device, config[0],
+        ))
+    }
+
+    pub fn register_tiled_tropical_matmul(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        n: usize,
+    ) -> io::Result<RegisterTiledTropicalMatmulKernel> {
+        let limits = device.limits();
+        let candidates = [4, 8]
+            .into_iter()
+            .filter(|&m| 2 * 256 * m * 4 <= limits.max_compute_workgroup_storage_size as usize)
+            .map(|m| vec![m])
+            .collect::<Vec<_>>();
+        let config = self.tune("register_tiled_tropical_matmul", n, &candidates, |config| {
+            measure_tropical_matmul(
+                device,
+                queue,
+                RegisterTiledTropicalMatmulKernel::with_micro_tile_size(device, config[0]),
+                n,
+            )
+        })?;
+        Ok(RegisterTiledTropicalMatmulKernel::with_micro_tile_size(
+            device, config[0],
+        ))
+    }
+
+    fn key(&self, name: &str, n: usize) -> String {
+        format!("{}|{}|{}", self.adapter, name, n)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let content = self
+            .cache
+            .iter()
+            .map(|(key, config)| {
+                let config = config.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                format!("{}\t{}\n", key, config.join(","))
+            })
+            .collect::<String>();
+        fs::write(&self.path, content)
+    }
+}
+
+fn measure_tropical_matmul<K: TropicalMatmulKernel>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    kernel: K,
+    n: usize,
+) -> Duration {
+    let tm = TropicalMatmul::new(kernel);
+    let (in_buffer, out_buffer) = tm.create_buffer(device, n);
+    tm.stage_adjacency_matrix(device, queue, &tuning_graph(n), n, &in_buffer);
+    time(device, || {
+        tm.run(device, queue, &in_buffer, &out_buffer, n);
+    })
+}
+
+// Paths have the largest diameter, so no kernel can stop early.
+fn tuning_graph(n: usize) -> Vec<(usize, usize)> {
+    (1..n).map(|i| (i - 1, i)).collect()
+}
+
+// Wall clock time of `f` after one warm-up call, waiting for the GPU.
+fn time<F: FnMut()>(device: &wgpu::Device, mut f: F) -> Duration {
+    f();
+    device.poll(wgpu::Maintain::Wait);
+    let start = Instant::now();
+    f();
+    device.poll(wgpu::Maintain::Wait);
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init;
+
+    fn adapter_info() -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: "test adapter".into(),
+            vendor: 1,
+            device: 2,
+            device_type: wgpu::DeviceType::Cpu,
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    #[test]
+    fn test_tune_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("autotune.tsv");
+        let candidates = vec![vec![4, 4], vec![8, 2], vec![16, 1]];
+        let mut calls = 0;
+        let mut autotuner = Autotuner::new(&path, &adapter_info()).unwrap();
+        let best = autotuner
+            .tune("kernel", 100, &candidates, |config| {
+                calls += 1;
+                Duration::from_millis(config[1] as u64)
+            })
+            .unwrap();
+        assert_eq!(best, vec![16, 1]);
+        assert_eq!(calls, 3);
+
+        let mut autotuner = Autotuner::new(&path, &adapter_info()).unwrap();
+        assert_eq!(autotuner.cached("kernel", 100), Some(&[16, 1][..]));
+        assert_eq!(autotuner.cached("kernel", 200), None);
+        let best = autotuner
+            .tune("kernel", 100, &candidates, |_| {
+                calls += 1;
+                Duration::ZERO
+            })
+            .unwrap();
+        assert_eq!(best, vec![16, 1]);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_tune_no_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("autotune.tsv");
+        let mut autotuner = Autotuner::new(&path, &adapter_info()).unwrap();
+        let error = autotuner
+            .tune("kernel", 100, &[], |_| Duration::ZERO)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(autotuner.cached("kernel", 100), None);
+    }
+
+    #[tokio::test]
+    async fn test_autotune_kernels() {
+        let (device, queue) = init().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("autotune.tsv");
+        let mut autotuner = Autotuner::new(&path, &adapter_info()).unwrap();
+        let n = 64;
+        autotuner.warshall_floyd::<f32>(&device, &queue, n).unwrap();
+        autotuner
+            .blocked_tropical_matmul::<u16>(&device, &queue, n)
+            .unwrap();
+        autotuner
+            .register_tiled_tropical_matmul(&device, &queue, n)
+            .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+    }
+}
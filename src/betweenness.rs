@@ -0,0 +1,349 @@
+use crate::{
+    buffer::download_buffer,
+    kernel::{create_bind_group, Kernel},
+};
+
+pub struct PathCountingKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl PathCountingKernel {
+    const WORKGROUP_SIZE: usize = 16;
+
+    pub fn new(device: &wgpu::Device) -> PathCountingKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("path_counting.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "path_counting",
+        });
+        PathCountingKernel { pipeline }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(
+            device,
+            &self.pipeline,
+            &[(0, in_buffer), (1, out_buffer), (2, params_buffer)],
+        )
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+    }
+
+    pub fn buffer_size(n: usize) -> usize {
+        Betweenness::stride(n) * Betweenness::stride(n) * 8
+    }
+}
+
+impl Kernel for PathCountingKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [Self::WORKGROUP_SIZE, Self::WORKGROUP_SIZE, 1]
+    }
+}
+
+pub struct BetweennessKernel {
+    dependency_pipeline: wgpu::ComputePipeline,
+    accumulate_pipeline: wgpu::ComputePipeline,
+}
+
+impl BetweennessKernel {
+    pub fn new(device: &wgpu::Device) -> BetweennessKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("betweenness.wgsl"));
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                module: &module,
+                layout: None,
+                entry_point,
+            })
+        };
+        BetweennessKernel {
+            dependency_pipeline: create_pipeline("dependency"),
+            accumulate_pipeline: create_pipeline("accumulate"),
+        }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        counts_buffer: &wgpu::Buffer,
+        partial_buffer: &wgpu::Buffer,
+        betweenness_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> [wgpu::BindGroup; 2] {
+        [
+            create_bind_group(
+                device,
+                &self.dependency_pipeline,
+                &[(0, counts_buffer), (1, partial_buffer), (3, params_buffer)],
+            ),
+            create_bind_group(
+                device,
+                &self.accumulate_pipeline,
+                &[
+                    (1, partial_buffer),
+                    (2, betweenness_buffer),
+                    (3, params_buffer),
+                ],
+            ),
+        ]
+    }
+
+    pub fn run(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_groups: &[wgpu::BindGroup; 2],
+        n: usize,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.dependency_pipeline);
+        pass.set_bind_group(0, &bind_groups[0], &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+        pass.set_pipeline(&self.accumulate_pipeline);
+        pass.set_bind_group(0, &bind_groups[1], &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+    }
+}
+
+impl Kernel for BetweennessKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [16, 16, 1]
+    }
+}
+
+pub struct Betweenness {
+    path_counting: PathCountingKernel,
+    betweenness: BetweennessKernel,
+}
+
+impl Betweenness {
+    pub fn new(device: &wgpu::Device) -> Betweenness {
+        Betweenness {
+            path_counting: PathCountingKernel::new(device),
+            betweenness: BetweennessKernel::new(device),
+        }
+    }
+
+    pub fn stride(n: usize) -> usize {
+        n.div_ceil(PathCountingKernel::WORKGROUP_SIZE) * PathCountingKernel::WORKGROUP_SIZE
+    }
+
+    pub fn create_buffer(&self, device: &wgpu::Device, n: usize) -> (wgpu::Buffer, wgpu::Buffer) {
+        let size = PathCountingKernel::buffer_size(n);
+        let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        (in_buffer, out_buffer)
+    }
+
+    pub fn stage_adjacency_matrix(
+        &self,
+        queue: &wgpu::Queue,
+        edges: &[(usize, usize)],
+        n: usize,
+        dst: &wgpu::Buffer,
+    ) {
+        let stride = Self::stride(n);
+        let mut counts = vec![[f32::INFINITY, 0.]; stride * stride];
+        for i in 0..n {
+            counts[i * stride + i] = [0., 1.];
+        }
+        for &(i, j) in edges.iter() {
+            counts[i * stride + j] = [1., 1.];
+            counts[j * stride + i] = [1., 1.];
+        }
+        queue.write_buffer(dst, 0, bytemuck::cast_slice(&counts));
+    }
+
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+    ) {
+        let size = PathCountingKernel::buffer_size(n);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self
+            .path_counting
+            .bind(device, in_buffer, out_buffer, &params_buffer);
+
+        let mut params_buffer_staging = wgpu::util::StagingBelt::new(8);
+        for k in 0..n {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            let params = vec![Self::stride(n) as u32, k as u32];
+            params_buffer_staging
+                .write_buffer(
+                    &mut encoder,
+                    &params_buffer,
+                    0,
+                    std::num::NonZeroU64::new(8).unwrap(),
+                    device,
+                )
+                .copy_from_slice(bytemuck::cast_slice(&params));
+            params_buffer_staging.finish();
+            if k > 0 {
+                encoder.copy_buffer_to_buffer(out_buffer, 0, in_buffer, 0, size as u64);
+            }
+            self.path_counting.run(&mut encoder, &bind_group, n);
+            queue.submit(Some(encoder.finish()));
+            params_buffer_staging.recall();
+        }
+    }
+
+    // `counts_buffer` is the output of `run`. Scores follow the undirected
+    // convention of counting each unordered pair once.
+    pub async fn betweenness(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        counts_buffer: &wgpu::Buffer,
+        n: usize,
+    ) -> Vec<f32> {
+        let stride = Self::stride(n);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let partial_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (stride * stride * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let betweenness_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (n * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let params = vec![n as u32, stride as u32];
+        queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+        let bind_groups = self.betweenness.bind(
+            device,
+            counts_buffer,
+            &partial_buffer,
+            &betweenness_buffer,
+            &params_buffer,
+        );
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.betweenness.run(&mut encoder, &bind_groups, n);
+        queue.submit(Some(encoder.finish()));
+        download_buffer(device, queue, &betweenness_buffer)
+            .await
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init;
+    use rand::{Rng, SeedableRng};
+    use std::collections::VecDeque;
+
+    fn brandes(edges: &[(usize, usize)], n: usize) -> Vec<f32> {
+        let mut neighbors = vec![vec![]; n];
+        for &(u, v) in edges {
+            neighbors[u].push(v);
+            neighbors[v].push(u);
+        }
+        let mut betweenness = vec![0.; n];
+        for s in 0..n {
+            let mut stack = vec![];
+            let mut predecessors = vec![vec![]; n];
+            let mut sigma = vec![0.; n];
+            let mut distance = vec![usize::MAX; n];
+            sigma[s] = 1.;
+            distance[s] = 0;
+            let mut queue = VecDeque::from([s]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                for &w in &neighbors[v] {
+                    if distance[w] == usize::MAX {
+                        distance[w] = distance[v] + 1;
+                        queue.push_back(w);
+                    }
+                    if distance[w] == distance[v] + 1 {
+                        sigma[w] += sigma[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+            let mut delta = vec![0.; n];
+            while let Some(w) = stack.pop() {
+                for &v in &predecessors[w] {
+                    delta[v] += sigma[v] / sigma[w] * (1. + delta[w]);
+                }
+                if w != s {
+                    betweenness[w] += delta[w];
+                }
+            }
+        }
+        betweenness.iter().map(|b| b / 2.).collect()
+    }
+
+    #[tokio::test]
+    async fn test_betweenness() {
+        let (device, queue) = init().await;
+        let betweenness = Betweenness::new(&device);
+        let n = 40;
+        for seed in 0..3 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let edges = (0..n)
+                .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+                .filter(|_| rng.gen_bool(0.1))
+                .collect::<Vec<_>>();
+            let (in_buffer, out_buffer) = betweenness.create_buffer(&device, n);
+            betweenness.stage_adjacency_matrix(&queue, &edges, n, &in_buffer);
+            betweenness.run(&device, &queue, &in_buffer, &out_buffer, n);
+            let result = betweenness
+                .betweenness(&device, &queue, &out_buffer, n)
+                .await;
+
+            let expected = brandes(&edges, n);
+            for (a, b) in result.iter().zip(&expected) {
+                assert!((a - b).abs() <= 1e-3 * b.max(1.), "{} != {}", a, b);
+            }
+        }
+    }
+}
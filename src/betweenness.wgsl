@@ -0,0 +1,67 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> counts: array<vec2<f32>>;
+@group(0)
+@binding(1)
+var<storage, read_write> partial: array<f32>;
+@group(0)
+@binding(2)
+var<storage, write> betweenness: array<f32>;
+@group(0)
+@binding(3)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(16, 16)
+fn dependency(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var v : u32 = global_invocation_id.x;
+  var s : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (v >= n || s >= n) {
+    return;
+  }
+  var sv : vec2<f32> = counts[s * stride + v];
+  var delta : f32 = 0.;
+  if (s != v && sv.x < 3.4e38) {
+    var t : u32 = 0u;
+    loop {
+      if (t >= n) {
+        break;
+      }
+      var vt : vec2<f32> = counts[v * stride + t];
+      var st : vec2<f32> = counts[s * stride + t];
+      if (t != s && t != v && st.x < 3.4e38 && sv.x + vt.x == st.x) {
+        delta = delta + sv.y * vt.y / st.y;
+      }
+      t = t + 1u;
+    }
+  }
+  partial[s * stride + v] = delta;
+}
+
+@compute
+@workgroup_size(16)
+fn accumulate(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var v : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (v >= n) {
+    return;
+  }
+  var b : f32 = 0.;
+  var s : u32 = 0u;
+  loop {
+    if (s >= n) {
+      break;
+    }
+    b = b + partial[s * stride + v];
+    s = s + 1u;
+  }
+  betweenness[v] = b / 2.;
+}
@@ -0,0 +1,244 @@
+use crate::{
+    buffer::{download_buffer, stage_adjacency_matrix},
+    kernel::{create_bind_group, Kernel},
+};
+use wgpu::util::DeviceExt;
+
+pub struct BfsKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl BfsKernel {
+    pub fn new(device: &wgpu::Device) -> BfsKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("bfs.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "bfs",
+        });
+        BfsKernel { pipeline }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        offsets_buffer: &wgpu::Buffer,
+        neighbors_buffer: &wgpu::Buffer,
+        distance_buffer: &wgpu::Buffer,
+        last_level_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(
+            device,
+            &self.pipeline,
+            &[
+                (0, offsets_buffer),
+                (1, neighbors_buffer),
+                (2, distance_buffer),
+                (3, last_level_buffer),
+                (4, params_buffer),
+            ],
+        )
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+    }
+}
+
+impl Kernel for BfsKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [16, 16, 1]
+    }
+}
+
+// All-pairs shortest paths of an unweighted graph by a breadth-first search
+// from every vertex at once, one dispatch per level. Only needs as many
+// dispatches as the diameter instead of one per vertex.
+pub struct Bfs {
+    kernel: BfsKernel,
+}
+
+impl Bfs {
+    // Levels submitted between checks for an empty frontier.
+    const LEVELS_PER_SUBMISSION: usize = 16;
+
+    pub fn new(device: &wgpu::Device) -> Bfs {
+        let kernel = BfsKernel::new(device);
+        Bfs { kernel }
+    }
+
+    pub fn stride(n: usize) -> usize {
+        n
+    }
+
+    pub fn buffer_size(n: usize) -> usize {
+        Self::stride(n) * n * 4
+    }
+
+    pub fn max_n(limits: &wgpu::Limits) -> usize {
+        let max_size = limits.max_storage_buffer_binding_size as usize;
+        let mut n = ((max_size / 4) as f64).sqrt() as usize;
+        while n > 0 && Self::buffer_size(n) > max_size {
+            n -= 1;
+        }
+        n
+    }
+
+    pub fn create_buffer(&self, device: &wgpu::Device, n: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: Self::buffer_size(n) as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    // Writes the distance matrix of the undirected graph `edges` into
+    // `distance_buffer`.
+    pub async fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        edges: &[(usize, usize)],
+        n: usize,
+        distance_buffer: &wgpu::Buffer,
+    ) {
+        let stride = Self::stride(n);
+        stage_adjacency_matrix::<f32>(device, queue, &[], n, stride, n, distance_buffer);
+        if n < 2 {
+            return;
+        }
+
+        let (offsets, neighbors) = csr(edges, n);
+        let offsets_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&offsets),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let neighbors_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&neighbors),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let last_level_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[0u32]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Distances are below n, so levels 0 to n - 2 can reach new vertices.
+        let levels = (0..n as u32 - 1)
+            .map(|level| [n as u32, stride as u32, level, 0])
+            .collect::<Vec<_>>();
+        let level_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&levels),
+            usage: wgpu::BufferUsages::COPY_SRC,
+        });
+        let bind_group = self.kernel.bind(
+            device,
+            &offsets_buffer,
+            &neighbors_buffer,
+            distance_buffer,
+            &last_level_buffer,
+            &params_buffer,
+        );
+
+        let mut start = 0;
+        while start < levels.len() {
+            let end = (start + Self::LEVELS_PER_SUBMISSION).min(levels.len());
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            for level in start..end {
+                encoder.copy_buffer_to_buffer(
+                    &level_params_buffer,
+                    (level * 16) as u64,
+                    &params_buffer,
+                    0,
+                    16,
+                );
+                self.kernel.run(&mut encoder, &bind_group, n);
+            }
+            queue.submit(Some(encoder.finish()));
+            let last_level = download_buffer::<u32>(device, queue, &last_level_buffer)
+                .await
+                .unwrap()[0] as usize;
+            // The frontier emptied before the last level of this submission.
+            if last_level < end {
+                break;
+            }
+            start = end;
+        }
+    }
+}
+
+// Compressed adjacency lists of both directions of every edge. `neighbors`
+// is never empty since storage bindings cannot be.
+fn csr(edges: &[(usize, usize)], n: usize) -> (Vec<u32>, Vec<u32>) {
+    let mut offsets = vec![0u32; n + 1];
+    for &(u, v) in edges {
+        offsets[u + 1] += 1;
+        offsets[v + 1] += 1;
+    }
+    for i in 0..n {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut position = offsets.clone();
+    let mut neighbors = vec![0u32; (edges.len() * 2).max(1)];
+    for &(u, v) in edges {
+        neighbors[position[u] as usize] = v as u32;
+        position[u] += 1;
+        neighbors[position[v] as usize] = u as u32;
+        position[v] += 1;
+    }
+    (offsets, neighbors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init;
+    use rand::{Rng, SeedableRng};
+
+    #[tokio::test]
+    async fn test_bfs() {
+        let (device, queue) = init().await;
+        let n = 60;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let edges = (0..n)
+            .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+            .filter(|_| rng.gen_bool(0.04))
+            .collect::<Vec<_>>();
+        let bfs = Bfs::new(&device);
+        let distance_buffer = bfs.create_buffer(&device, n);
+        bfs.run(&device, &queue, &edges, n, &distance_buffer).await;
+        let result = download_buffer::<f32>(&device, &queue, &distance_buffer)
+            .await
+            .unwrap();
+        assert_eq!(result, crate::cpu::warshall_floyd::<f32>(&edges, n));
+
+        // Diameter beyond a single submission.
+        let edges = crate::testing::path_graph(n);
+        bfs.run(&device, &queue, &edges, n, &distance_buffer).await;
+        let result = download_buffer::<f32>(&device, &queue, &distance_buffer)
+            .await
+            .unwrap();
+        assert_eq!(result, crate::cpu::warshall_floyd::<f32>(&edges, n));
+    }
+}
@@ -0,0 +1,49 @@
+struct Params {
+  n : u32,
+  stride : u32,
+  level : u32,
+  pad : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> offsets: array<u32>;
+@group(0)
+@binding(1)
+var<storage, read> neighbors: array<u32>;
+@group(0)
+@binding(2)
+var<storage, read_write> distance: array<f32>;
+@group(0)
+@binding(3)
+var<storage, read_write> last_level: atomic<u32>;
+@group(0)
+@binding(4)
+var<uniform> params: Params;
+
+// Expands the frontier of source y by one level: vertex x is reached at
+// level + 1 if any neighbor of it was reached at level.
+@compute
+@workgroup_size(16, 16)
+fn bfs(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  var level : f32 = f32(params.level);
+  if (x >= n || y >= n || distance[y * stride + x] <= level) {
+    return;
+  }
+  var i : u32 = offsets[x];
+  loop {
+    if (i >= offsets[x + 1u]) {
+      break;
+    }
+    if (distance[y * stride + neighbors[i]] == level) {
+      distance[y * stride + x] = level + 1.;
+      atomicMax(&last_level, params.level + 1u);
+      return;
+    }
+    i = i + 1u;
+  }
+}
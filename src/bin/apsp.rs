@@ -0,0 +1,354 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    process,
+    time::{Duration, Instant},
+};
+use wgpu_test::{
+    apsp, cpu,
+    graph::Graph,
+    io::{self, CsvOptions, EdgeListOptions, Indexing},
+    matrix::DistanceMatrix,
+    metrics::GraphMetrics,
+};
+
+const USAGE: &str = "\
+usage: apsp [options] <input>
+
+Computes all-pairs shortest path lengths. Edge weights and directions are
+kept, except by bfs, which only accepts undirected, unweighted graphs.
+
+options:
+  --input-format <f>  edgelist, mtx, dimacs or metis (default: by file extension)
+  --indexing <i>      vertices of edge lists: zero, one or labels (default: zero)
+  --directed          read edge lists as directed graphs
+  --algo <a>          wf, tropical-naive, tropical-block, bfs or cpu (default: wf)
+  --backend <b>       comma separated wgpu backends: vulkan, metal, dx12, dx11,
+                      gl, webgpu, primary, secondary or all (default: all)
+  --format <f>        tsv, csv, npy, metrics or ss, a DIMACS style summary
+                      (default: tsv)
+  --infinity <s>      written for unreachable pairs in tsv and csv (default: inf)
+  -o, --output <path> output file (default: standard output)
+  --timing            print the time of every phase to standard error
+  -h, --help          print this message
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InputFormat {
+    EdgeList,
+    MatrixMarket,
+    Dimacs,
+    Metis,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    WarshallFloyd,
+    TropicalNaive,
+    TropicalBlock,
+    Bfs,
+    Cpu,
+}
+
+impl Algorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::WarshallFloyd => "wf",
+            Algorithm::TropicalNaive => "tropical-naive",
+            Algorithm::TropicalBlock => "tropical-block",
+            Algorithm::Bfs => "bfs",
+            Algorithm::Cpu => "cpu",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Tsv,
+    Csv,
+    Npy,
+    Metrics,
+    Ss,
+}
+
+struct Options {
+    input: String,
+    input_format: Option<InputFormat>,
+    indexing: Indexing,
+    directed: bool,
+    algorithm: Algorithm,
+    backends: wgpu::Backends,
+    format: OutputFormat,
+    output: Option<String>,
+    infinity: String,
+    timing: bool,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut input = None;
+        let mut options = Options {
+            input: String::new(),
+            input_format: None,
+            indexing: Indexing::ZeroBased,
+            directed: false,
+            algorithm: Algorithm::WarshallFloyd,
+            backends: wgpu::Backends::all(),
+            format: OutputFormat::Tsv,
+            output: None,
+            infinity: "inf".into(),
+            timing: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--input-format" => {
+                    options.input_format = Some(match value()?.as_str() {
+                        "edgelist" => InputFormat::EdgeList,
+                        "mtx" => InputFormat::MatrixMarket,
+                        "dimacs" => InputFormat::Dimacs,
+                        "metis" => InputFormat::Metis,
+                        other => return Err(format!("unknown input format {}", other)),
+                    })
+                }
+                "--indexing" => {
+                    options.indexing = match value()?.as_str() {
+                        "zero" => Indexing::ZeroBased,
+                        "one" => Indexing::OneBased,
+                        "labels" => Indexing::Labels,
+                        other => return Err(format!("unknown indexing {}", other)),
+                    }
+                }
+                "--directed" => options.directed = true,
+                "--algo" => {
+                    options.algorithm = match value()?.as_str() {
+                        "wf" => Algorithm::WarshallFloyd,
+                        "tropical-naive" => Algorithm::TropicalNaive,
+                        "tropical-block" => Algorithm::TropicalBlock,
+                        "bfs" => Algorithm::Bfs,
+                        "cpu" => Algorithm::Cpu,
+                        other => return Err(format!("unknown algorithm {}", other)),
+                    }
+                }
+                "--backend" => {
+                    let value = value()?;
+                    options.backends = wgpu::util::parse_backends_from_comma_list(&value);
+                    if options.backends.is_empty() {
+                        return Err(format!("unknown backend {}", value));
+                    }
+                }
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "tsv" => OutputFormat::Tsv,
+                        "csv" => OutputFormat::Csv,
+                        "npy" => OutputFormat::Npy,
+                        "metrics" => OutputFormat::Metrics,
+                        "ss" => OutputFormat::Ss,
+                        other => return Err(format!("unknown output format {}", other)),
+                    }
+                }
+                "-o" | "--output" => options.output = Some(value()?),
+                "--infinity" => options.infinity = value()?,
+                "--timing" => options.timing = true,
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    return Err(format!("unknown option {}", arg))
+                }
+                _ if input.is_none() => input = Some(arg),
+                _ => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+        options.input = input.ok_or("missing input file")?;
+        Ok(options)
+    }
+
+    fn input_format(&self) -> InputFormat {
+        self.input_format.unwrap_or_else(|| {
+            match Path::new(&self.input).extension().and_then(|e| e.to_str()) {
+                Some("mtx") => InputFormat::MatrixMarket,
+                Some("gr") => InputFormat::Dimacs,
+                Some("graph" | "metis") => InputFormat::Metis,
+                _ => InputFormat::EdgeList,
+            }
+        })
+    }
+}
+
+struct Timer {
+    enabled: bool,
+    last: Instant,
+}
+
+impl Timer {
+    fn lap(&mut self, phase: &str) -> Duration {
+        let elapsed = self.last.elapsed();
+        if self.enabled {
+            eprintln!("{:<10} {:>12.3} ms", phase, elapsed.as_secs_f64() * 1e3);
+        }
+        self.last = Instant::now();
+        elapsed
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print!("{}", USAGE);
+        return;
+    }
+    let options = match Options::parse(args.into_iter()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("apsp: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(&options) {
+        eprintln!("apsp: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut timer = Timer {
+        enabled: options.timing,
+        last: Instant::now(),
+    };
+    let file = fs::File::open(&options.input).map_err(|e| format!("{}: {}", options.input, e))?;
+    let graph = read_graph(BufReader::new(file), options)
+        .map_err(|e| format!("{}: {}", options.input, e))?;
+    graph
+        .validate()
+        .map_err(|e| format!("{}: {}", options.input, e))?;
+    if graph.n == 0 {
+        return Err(format!("{}: graph has no vertices", options.input));
+    }
+    timer.lap("read");
+
+    let (distance, elapsed) = if options.algorithm == Algorithm::Cpu {
+        let distance = DistanceMatrix::new(graph.n, cpu::warshall_floyd_graph::<f32>(&graph));
+        (distance, timer.lap("compute"))
+    } else {
+        run_gpu(options, &graph, &mut timer)?
+    };
+
+    let mut output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(fs::File::create(path).map_err(|e| format!("{}: {}", path, e))?),
+        None => Box::new(std::io::stdout()),
+    };
+    let mut output = BufWriter::new(&mut output);
+    let csv_options = |separator| CsvOptions {
+        separator,
+        infinity: &options.infinity,
+        labels: graph.labels.as_deref(),
+    };
+    match options.format {
+        OutputFormat::Tsv => io::write_csv(&mut output, &distance, &csv_options('\t')),
+        OutputFormat::Csv => io::write_csv(&mut output, &distance, &csv_options(',')),
+        OutputFormat::Npy => io::write_npy(&mut output, &distance),
+        OutputFormat::Metrics => write_metrics(
+            &mut output,
+            &GraphMetrics::from_row_stats(
+                &cpu::row_stats(distance.as_slice(), graph.n),
+                graph.directed,
+            ),
+        ),
+        OutputFormat::Ss => io::write_dimacs_ss(
+            &mut output,
+            &graph,
+            &distance,
+            &(0..graph.n).collect::<Vec<_>>(),
+            options.algorithm.name(),
+            elapsed,
+        ),
+    }
+    .and_then(|_| output.flush())
+    .map_err(|e| e.to_string())?;
+    timer.lap("write");
+    Ok(())
+}
+
+// Returns the distance matrix and the compute time.
+fn run_gpu(
+    options: &Options,
+    graph: &Graph,
+    timer: &mut Timer,
+) -> Result<(DistanceMatrix, Duration), String> {
+    let n = graph.n;
+    let instance = wgpu::Instance::new(options.backends);
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok_or("no suitable adapter found")?;
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            features: wgpu::Features::empty(),
+            limits: adapter.limits(),
+        },
+        None,
+    ))
+    .map_err(|e| e.to_string())?;
+    if options.timing {
+        let info = adapter.get_info();
+        eprintln!("adapter    {} ({:?})", info.name, info.backend);
+    }
+    timer.lap("device");
+
+    // Includes staging the graph.
+    let algorithm = match options.algorithm {
+        Algorithm::WarshallFloyd => apsp::Algorithm::WarshallFloyd,
+        Algorithm::TropicalNaive => apsp::Algorithm::TropicalNaive,
+        Algorithm::TropicalBlock => apsp::Algorithm::TropicalBlock,
+        Algorithm::Bfs => apsp::Algorithm::Bfs,
+        Algorithm::Cpu => unreachable!(),
+    };
+    let (buffer, stride) = pollster::block_on(apsp::run(&device, &queue, graph, algorithm))
+        .map_err(|e| e.to_string())?;
+    device.poll(wgpu::Maintain::Wait);
+    let elapsed = timer.lap("compute");
+
+    let distance = pollster::block_on(DistanceMatrix::download(
+        &device, &queue, &buffer, n, stride,
+    ))
+    .ok_or("failed to download the distance matrix")?;
+    timer.lap("download");
+    Ok((distance, elapsed))
+}
+
+fn read_graph<R: BufRead>(reader: R, options: &Options) -> Result<Graph, String> {
+    match options.input_format() {
+        InputFormat::EdgeList => {
+            let options = EdgeListOptions {
+                indexing: options.indexing,
+                directed: options.directed,
+            };
+            io::read_edge_list(reader, &options).map_err(|e| e.to_string())
+        }
+        InputFormat::MatrixMarket => io::read_matrix_market(reader).map_err(|e| e.to_string()),
+        InputFormat::Dimacs => io::read_dimacs(reader).map_err(|e| e.to_string()),
+        InputFormat::Metis => io::read_metis(reader).map_err(|e| e.to_string()),
+    }
+}
+
+fn write_metrics<W: Write>(output: &mut W, metrics: &GraphMetrics) -> std::io::Result<()> {
+    let join = |values: &[usize]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    writeln!(output, "diameter\t{}", metrics.diameter)?;
+    writeln!(output, "radius\t{}", metrics.radius)?;
+    writeln!(output, "center\t{}", join(&metrics.center))?;
+    writeln!(output, "periphery\t{}", join(&metrics.periphery))?;
+    writeln!(
+        output,
+        "average_shortest_path_length\t{}",
+        metrics.average_shortest_path_length
+    )?;
+    writeln!(output, "wiener_index\t{}", metrics.wiener_index)
+}
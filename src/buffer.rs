@@ -1,19 +1,56 @@
-pub fn stage_adjacency_matrix(
+use crate::{
+    distance::{adjacency_matrix, graph_adjacency_matrix, Distance},
+    graph::Graph,
+    matrix::DistanceMatrix,
+};
+
+pub fn stage_adjacency_matrix<T: Distance>(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     edges: &[(usize, usize)],
     n: usize,
-    size: usize,
+    stride: usize,
+    rows: usize,
+    dst: &wgpu::Buffer,
+) {
+    upload(
+        device,
+        queue,
+        &adjacency_matrix::<T>(edges, n, stride, rows),
+        dst,
+    );
+}
+
+pub fn stage_graph<T: Distance>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    graph: &Graph,
+    stride: usize,
+    rows: usize,
     dst: &wgpu::Buffer,
 ) {
-    let mut distance = vec![std::f32::INFINITY; n * n];
-    for i in 0..n {
-        distance[i * n + i] = 0.;
-    }
-    for &(i, j) in edges.iter() {
-        distance[i * n + j] = 1.;
-        distance[j * n + i] = 1.;
-    }
+    upload(
+        device,
+        queue,
+        &graph_adjacency_matrix::<T>(graph, stride, rows),
+        dst,
+    );
+}
+
+// Stages a previously computed matrix, e.g. as a warm start.
+pub fn stage_distance_matrix<T: Distance>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    matrix: &DistanceMatrix<T>,
+    stride: usize,
+    rows: usize,
+    dst: &wgpu::Buffer,
+) {
+    upload(device, queue, &matrix.padded(stride, rows), dst);
+}
+
+fn upload<T: Distance>(device: &wgpu::Device, queue: &wgpu::Queue, data: &[T], dst: &wgpu::Buffer) {
+    let size = std::mem::size_of_val(data);
 
     let mut encoder =
         device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -21,12 +58,12 @@ pub fn stage_adjacency_matrix(
     staging
         .write_buffer(
             &mut encoder,
-            &dst,
+            dst,
             0,
             std::num::NonZeroU64::new(size as u64).unwrap(),
-            &device,
+            device,
         )
-        .copy_from_slice(bytemuck::cast_slice(&distance));
+        .copy_from_slice(bytemuck::cast_slice(data));
     staging.finish();
     queue.submit(Some(encoder.finish()));
 }
@@ -36,12 +73,38 @@ pub async fn download_distance_matrix(
     queue: &wgpu::Queue,
     src: &wgpu::Buffer,
 ) -> Option<Vec<f32>> {
-    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-    wgpu::util::DownloadBuffer::read_buffer(&device, &queue, &src.slice(..), move |result| {
-        let buffer = result.unwrap();
-        let result = bytemuck::cast_slice::<u8, f32>(&buffer).to_vec();
-        sender.send(result).ok();
-    });
+    download_buffer(device, queue, src).await
+}
+
+// Blocks the calling thread until the download completes. Use
+// `Poller::download_buffer` to await it without blocking. On the web polling
+// is a no-op and the browser resolves the mapping.
+pub async fn download_buffer<T: bytemuck::Pod + Send>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    src: &wgpu::Buffer,
+) -> Option<Vec<T>> {
+    let receiver = read_buffer(device, queue, src, ());
     device.poll(wgpu::Maintain::Wait);
     receiver.receive().await
 }
+
+// Copies `src` to a mappable buffer and sends its contents once mapped.
+// `guard` is dropped when the mapping callback runs.
+pub(crate) fn read_buffer<T: bytemuck::Pod + Send, G: Send + 'static>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    src: &wgpu::Buffer,
+    guard: G,
+) -> futures_intrusive::channel::shared::OneshotReceiver<Vec<T>> {
+    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+    wgpu::util::DownloadBuffer::read_buffer(device, queue, &src.slice(..), move |result| {
+        if let Ok(buffer) = result {
+            sender
+                .send(bytemuck::cast_slice::<u8, T>(&buffer).to_vec())
+                .ok();
+        }
+        drop(guard);
+    });
+    receiver
+}
@@ -0,0 +1,100 @@
+use crate::metrics::{row_stats, RowStatsKernel};
+
+pub struct Centrality {
+    kernel: RowStatsKernel,
+}
+
+impl Centrality {
+    pub fn new(device: &wgpu::Device) -> Centrality {
+        let kernel = RowStatsKernel::new(device);
+        Centrality { kernel }
+    }
+
+    // Uses the Wasserman-Faust scaling, so vertices in small components
+    // are not ranked above those in the giant component.
+    pub async fn closeness(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+    ) -> Vec<f32> {
+        let stats = row_stats(&self.kernel, device, queue, distance_buffer, n, stride).await;
+        stats
+            .iter()
+            .map(|s| {
+                if s.sum > 0. {
+                    let r = s.reachable as f32;
+                    r / (n - 1) as f32 * r / s.sum
+                } else {
+                    0.
+                }
+            })
+            .collect()
+    }
+
+    pub async fn harmonic(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+    ) -> Vec<f32> {
+        let stats = row_stats(&self.kernel, device, queue, distance_buffer, n, stride).await;
+        stats.iter().map(|s| s.harmonic).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{init, path_graph, warshall_floyd},
+        warshall_floyd::WarshallFloydKernel,
+    };
+
+    fn assert_all_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, b) in actual.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_centrality_path() {
+        let (device, queue) = init().await;
+        let n = 4;
+        let stride = WarshallFloydKernel::stride_x(n);
+        let distance_buffer = warshall_floyd(&device, &queue, &path_graph(n), n);
+        let centrality = Centrality::new(&device);
+
+        let closeness = centrality
+            .closeness(&device, &queue, &distance_buffer, n, stride)
+            .await;
+        assert_all_close(&closeness, &[0.5, 0.75, 0.75, 0.5]);
+        let harmonic = centrality
+            .harmonic(&device, &queue, &distance_buffer, n, stride)
+            .await;
+        assert_all_close(&harmonic, &[11. / 6., 2.5, 2.5, 11. / 6.]);
+    }
+
+    #[tokio::test]
+    async fn test_centrality_disconnected() {
+        let (device, queue) = init().await;
+        let n = 6;
+        let stride = WarshallFloydKernel::stride_x(n);
+        let distance_buffer = warshall_floyd(&device, &queue, &[(0, 1), (1, 2), (3, 4)], n);
+        let centrality = Centrality::new(&device);
+
+        let closeness = centrality
+            .closeness(&device, &queue, &distance_buffer, n, stride)
+            .await;
+        assert_all_close(&closeness, &[4. / 15., 0.4, 4. / 15., 0.2, 0.2, 0.]);
+        let harmonic = centrality
+            .harmonic(&device, &queue, &distance_buffer, n, stride)
+            .await;
+        assert_all_close(&harmonic, &[1.5, 2., 1.5, 1., 1., 0.]);
+    }
+}
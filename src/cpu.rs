@@ -0,0 +1,56 @@
+use crate::{
+    distance::{adjacency_matrix, graph_adjacency_matrix, Distance},
+    graph::Graph,
+    metrics::RowStats,
+};
+
+// Reference implementation returning the dense `n * n` distance matrix.
+pub fn warshall_floyd<T: Distance>(edges: &[(usize, usize)], n: usize) -> Vec<T> {
+    relax(adjacency_matrix::<T>(edges, n, n, n), n)
+}
+
+// Same as `warshall_floyd`, honoring the weights and direction of `graph`.
+pub fn warshall_floyd_graph<T: Distance>(graph: &Graph) -> Vec<T> {
+    relax(
+        graph_adjacency_matrix::<T>(graph, graph.n, graph.n),
+        graph.n,
+    )
+}
+
+fn relax<T: Distance>(mut distance: Vec<T>, n: usize) -> Vec<T> {
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                let d = distance[i * n + k].add(distance[k * n + j]);
+                if d < distance[i * n + j] {
+                    distance[i * n + j] = d;
+                }
+            }
+        }
+    }
+    distance
+}
+
+// Reference implementation of the row statistics computed by
+// `metrics::RowStatsKernel`.
+pub fn row_stats(distance: &[f32], n: usize) -> Vec<RowStats> {
+    (0..n)
+        .map(|i| {
+            let mut stats = RowStats {
+                eccentricity: 0.,
+                sum: 0.,
+                reachable: 0,
+                harmonic: 0.,
+            };
+            for (j, &d) in distance[i * n..(i + 1) * n].iter().enumerate() {
+                if j != i && d.is_finite() {
+                    stats.eccentricity = stats.eccentricity.max(d);
+                    stats.sum += d;
+                    stats.reachable += 1;
+                    stats.harmonic += 1. / d;
+                }
+            }
+            stats
+        })
+        .collect()
+}
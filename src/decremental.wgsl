@@ -0,0 +1,173 @@
+struct Params {
+  n : u32,
+  stride : u32,
+  num_removed : u32,
+  pad : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read_write> distance: array<f32>;
+@group(0)
+@binding(1)
+var<storage, read> weight: array<f32>;
+@group(0)
+@binding(2)
+var<storage, read_write> predecessor: array<u32>;
+@group(0)
+@binding(3)
+var<storage, read_write> counter: array<atomic<u32>>;
+@group(0)
+@binding(4)
+var<storage, read> removed: array<vec2<u32>>;
+@group(0)
+@binding(5)
+var<uniform> params: Params;
+
+// Affected pairs are reachable pairs that `mark` reset to infinity. Once
+// relaxed they carry this bit in `predecessor` until `finish`.
+let AFFECTED : u32 = 0x80000000u;
+let NONE : u32 = 0xffffffffu;
+
+@compute
+@workgroup_size(16, 16)
+fn predecessors(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x >= n || y >= n) {
+    return;
+  }
+  var best : f32 = distance[y * stride + x];
+  var p : u32 = NONE;
+  if (x == y) {
+    p = y;
+  } else if (best < 3.4e38) {
+    best = bitcast<f32>(0x7f800000u);
+    var k : u32 = 0u;
+    loop {
+      if (k >= n) {
+        break;
+      }
+      var w : f32 = weight[k * stride + x];
+      if (k != x && w < 3.4e38) {
+        var d : f32 = distance[y * stride + k] + w;
+        if (d < best) {
+          best = d;
+          p = k;
+        }
+      }
+      k = k + 1u;
+    }
+  }
+  predecessor[y * stride + x] = p;
+}
+
+// Walks the shortest path tree of row y back from x and invalidates the
+// pair if the path crosses one of the removed edges.
+@compute
+@workgroup_size(16, 16)
+fn mark(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x >= n || y >= n) {
+    return;
+  }
+  var hit : bool = false;
+  var j : u32 = x;
+  var steps : u32 = 0u;
+  loop {
+    if (hit || j == y || steps >= n) {
+      break;
+    }
+    var p : u32 = predecessor[y * stride + j];
+    if (p == NONE) {
+      break;
+    }
+    var e : u32 = 0u;
+    loop {
+      if (e >= params.num_removed) {
+        break;
+      }
+      var edge : vec2<u32> = removed[e];
+      if ((p == edge.x && j == edge.y) || (p == edge.y && j == edge.x)) {
+        hit = true;
+      }
+      e = e + 1u;
+    }
+    j = p;
+    steps = steps + 1u;
+  }
+  if (hit) {
+    distance[y * stride + x] = bitcast<f32>(0x7f800000u);
+    atomicAdd(&counter[y], 1u);
+  }
+}
+
+// Relaxes the affected pairs in place. Distances only decrease and always
+// stay the length of some path, so reading neighbours mid-update is safe.
+@compute
+@workgroup_size(16, 16)
+fn relax(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x >= n || y >= n) {
+    return;
+  }
+  if (atomicLoad(&counter[y]) == 0u) {
+    return;
+  }
+  var current : f32 = distance[y * stride + x];
+  var q : u32 = predecessor[y * stride + x];
+  if (q == NONE || ((q & AFFECTED) == 0u && current < 3.4e38)) {
+    return;
+  }
+  var best : f32 = current;
+  var p : u32 = 0u;
+  var k : u32 = 0u;
+  loop {
+    if (k >= n) {
+      break;
+    }
+    var w : f32 = weight[k * stride + x];
+    if (k != x && w < 3.4e38) {
+      var d : f32 = distance[y * stride + k] + w;
+      if (d < best) {
+        best = d;
+        p = k;
+      }
+    }
+    k = k + 1u;
+  }
+  if (best < current) {
+    distance[y * stride + x] = best;
+    predecessor[y * stride + x] = p | AFFECTED;
+    atomicAdd(&counter[n], 1u);
+  }
+}
+
+@compute
+@workgroup_size(16, 16)
+fn finish(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x >= n || y >= n) {
+    return;
+  }
+  var p : u32 = predecessor[y * stride + x];
+  if (p == NONE) {
+    return;
+  }
+  if (distance[y * stride + x] < 3.4e38) {
+    predecessor[y * stride + x] = p & ~AFFECTED;
+  } else {
+    predecessor[y * stride + x] = NONE;
+  }
+}
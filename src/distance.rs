@@ -0,0 +1,197 @@
+use crate::graph::Graph;
+use std::borrow::Cow;
+
+// Element type of a distance matrix. Shaders operate on storage words that
+// hold `LANES` elements each; `WGSL` defines the types and helpers they use:
+//
+//   Word, Scalar, Lanes           storage word, one element, unpacked word
+//   unpack(Word) -> Lanes         pack(Lanes) -> Word
+//   element(Word, u32) -> Scalar  splat(Scalar) -> Lanes
+//   add(Lanes, Lanes) -> Lanes    infinity() -> Lanes
+pub trait Distance: bytemuck::Pod + PartialOrd + Send + std::fmt::Debug {
+    const LANES: usize;
+    const INFINITY: Self;
+    const ZERO: Self;
+    const ONE: Self;
+    const WGSL: &'static str;
+
+    fn add(self, other: Self) -> Self;
+
+    // Nearest finite distance to a non-negative edge weight.
+    fn from_weight(weight: f32) -> Self;
+}
+
+impl Distance for f32 {
+    const LANES: usize = 1;
+    const INFINITY: f32 = f32::INFINITY;
+    const ZERO: f32 = 0.;
+    const ONE: f32 = 1.;
+    const WGSL: &'static str = "
+type Word = f32;
+type Scalar = f32;
+type Lanes = f32;
+let LANES : u32 = 1u;
+fn unpack(w : Word) -> Lanes { return w; }
+fn pack(l : Lanes) -> Word { return l; }
+fn element(w : Word, i : u32) -> Scalar { return w; }
+fn splat(s : Scalar) -> Lanes { return s; }
+fn add(a : Lanes, b : Lanes) -> Lanes { return a + b; }
+fn infinity() -> Lanes { return bitcast<f32>(0x7f800000u); }
+";
+
+    fn add(self, other: f32) -> f32 {
+        self + other
+    }
+
+    fn from_weight(weight: f32) -> f32 {
+        weight
+    }
+}
+
+impl Distance for u32 {
+    const LANES: usize = 1;
+    const INFINITY: u32 = u32::MAX;
+    const ZERO: u32 = 0;
+    const ONE: u32 = 1;
+    const WGSL: &'static str = "
+type Word = u32;
+type Scalar = u32;
+type Lanes = u32;
+let LANES : u32 = 1u;
+fn unpack(w : Word) -> Lanes { return w; }
+fn pack(l : Lanes) -> Word { return l; }
+fn element(w : Word, i : u32) -> Scalar { return w; }
+fn splat(s : Scalar) -> Lanes { return s; }
+fn add(a : Lanes, b : Lanes) -> Lanes {
+  let s = a + b;
+  return select(s, 0xffffffffu, s < a);
+}
+fn infinity() -> Lanes { return 0xffffffffu; }
+";
+
+    fn add(self, other: u32) -> u32 {
+        self.saturating_add(other)
+    }
+
+    fn from_weight(weight: f32) -> u32 {
+        weight.round().min((u32::MAX - 1) as f32) as u32
+    }
+}
+
+// Two elements per word, the even index in the low half.
+impl Distance for u16 {
+    const LANES: usize = 2;
+    const INFINITY: u16 = u16::MAX;
+    const ZERO: u16 = 0;
+    const ONE: u16 = 1;
+    const WGSL: &'static str = "
+type Word = u32;
+type Scalar = u32;
+type Lanes = vec2<u32>;
+let LANES : u32 = 2u;
+fn unpack(w : Word) -> Lanes { return vec2<u32>(w & 0xffffu, w >> 16u); }
+fn pack(l : Lanes) -> Word { return l.x | (l.y << 16u); }
+fn element(w : Word, i : u32) -> Scalar { return (w >> (16u * i)) & 0xffffu; }
+fn splat(s : Scalar) -> Lanes { return vec2<u32>(s); }
+fn add(a : Lanes, b : Lanes) -> Lanes { return min(a + b, vec2<u32>(0xffffu)); }
+fn infinity() -> Lanes { return vec2<u32>(0xffffu); }
+";
+
+    fn add(self, other: u16) -> u16 {
+        self.saturating_add(other)
+    }
+
+    fn from_weight(weight: f32) -> u16 {
+        weight.round().min((u16::MAX - 1) as f32) as u16
+    }
+}
+
+// Two halves per word as well, converted through f32 with the WGSL packing
+// builtins so that no shader-f16 support is needed.
+impl Distance for half::f16 {
+    const LANES: usize = 2;
+    const INFINITY: half::f16 = half::f16::INFINITY;
+    const ZERO: half::f16 = half::f16::ZERO;
+    const ONE: half::f16 = half::f16::ONE;
+    const WGSL: &'static str = "
+type Word = u32;
+type Scalar = f32;
+type Lanes = vec2<f32>;
+let LANES : u32 = 2u;
+fn unpack(w : Word) -> Lanes { return unpack2x16float(w); }
+fn pack(l : Lanes) -> Word { return pack2x16float(l); }
+fn element(w : Word, i : u32) -> Scalar {
+  let l = unpack2x16float(w);
+  return select(l.x, l.y, i == 1u);
+}
+fn splat(s : Scalar) -> Lanes { return vec2<f32>(s); }
+fn add(a : Lanes, b : Lanes) -> Lanes { return a + b; }
+fn infinity() -> Lanes { return vec2<f32>(bitcast<f32>(0x7f800000u)); }
+";
+
+    fn add(self, other: half::f16) -> half::f16 {
+        self + other
+    }
+
+    fn from_weight(weight: f32) -> half::f16 {
+        half::f16::from_f32(weight).min(half::f16::MAX)
+    }
+}
+
+pub(crate) fn create_shader_module<T: Distance>(
+    device: &wgpu::Device,
+    source: &str,
+) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{}\n{}", T::WGSL, source))),
+    })
+}
+
+// Host-side adjacency matrix with `stride` elements per row.
+pub(crate) fn adjacency_matrix<T: Distance>(
+    edges: &[(usize, usize)],
+    n: usize,
+    stride: usize,
+    rows: usize,
+) -> Vec<T> {
+    let mut distance = vec![T::INFINITY; stride * rows];
+    for i in 0..n {
+        distance[i * stride + i] = T::ZERO;
+    }
+    // Self-loops never shorten a path and must not overwrite the diagonal.
+    for &(i, j) in edges.iter().filter(|&&(i, j)| i != j) {
+        distance[i * stride + j] = T::ONE;
+        distance[j * stride + i] = T::ONE;
+    }
+    distance
+}
+
+// Same as `adjacency_matrix`, but keeps the direction and weights of the
+// edges of `graph`. Parallel edges keep the smallest weight.
+pub(crate) fn graph_adjacency_matrix<T: Distance>(
+    graph: &Graph,
+    stride: usize,
+    rows: usize,
+) -> Vec<T> {
+    let mut distance = adjacency_matrix::<T>(&[], graph.n, stride, rows);
+    for (e, &(i, j)) in graph.edges.iter().enumerate() {
+        if i == j {
+            continue;
+        }
+        let weight = graph
+            .weights
+            .as_ref()
+            .map_or(T::ONE, |weights| T::from_weight(weights[e]));
+        let mut relax = |index: usize| {
+            if weight < distance[index] {
+                distance[index] = weight;
+            }
+        };
+        relax(i * stride + j);
+        if !graph.directed {
+            relax(j * stride + i);
+        }
+    }
+    distance
+}
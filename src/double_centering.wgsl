@@ -0,0 +1,68 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> distance: array<f32>;
+@group(0)
+@binding(1)
+var<storage, read_write> mean: array<f32>;
+@group(0)
+@binding(2)
+var<storage, write> centered: array<f32>;
+@group(0)
+@binding(3)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(16)
+fn row_mean(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var i : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (i >= n) {
+    return;
+  }
+  var s : f32 = 0.;
+  var j : u32 = 0u;
+  loop {
+    if (j >= n) {
+      break;
+    }
+    var d : f32 = distance[i * stride + j];
+    s = s + d * d;
+    j = j + 1u;
+  }
+  mean[i] = s / f32(n);
+}
+
+@compute
+@workgroup_size(1)
+fn grand_mean() {
+  var n : u32 = params.n;
+  var s : f32 = 0.;
+  var i : u32 = 0u;
+  loop {
+    if (i >= n) {
+      break;
+    }
+    s = s + mean[i];
+    i = i + 1u;
+  }
+  mean[n] = s / f32(n);
+}
+
+@compute
+@workgroup_size(16, 16)
+fn double_centering(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x < n && y < n) {
+    var d : f32 = distance[y * stride + x];
+    centered[y * stride + x] = -0.5 * (d * d - mean[y] - mean[x] + mean[n]);
+  }
+}
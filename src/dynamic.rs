@@ -0,0 +1,593 @@
+use crate::{
+    buffer::download_buffer,
+    kernel::{create_bind_group, Kernel},
+    warshall_floyd::{stage_adjacency_matrix, WarshallFloyd, WarshallFloydKernel},
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct IncrementalParams {
+    n: u32,
+    stride: u32,
+    u: u32,
+    v: u32,
+    w: f32,
+    padding: [u32; 3],
+}
+
+// Rejects updates that would write outside the matrix or break the shortest
+// paths invariants, before anything is submitted.
+fn validate(edges: &[(usize, usize, f32)], n: usize) -> Result<(), String> {
+    for &(u, v, w) in edges {
+        if u >= n || v >= n {
+            return Err(format!("edge ({}, {}) out of range", u, v));
+        }
+        if w.is_nan() || w < 0. {
+            return Err(format!("invalid weight {}", w));
+        }
+    }
+    Ok(())
+}
+
+pub struct IncrementalKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl IncrementalKernel {
+    pub fn new(device: &wgpu::Device) -> IncrementalKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("incremental.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "incremental",
+        });
+        IncrementalKernel { pipeline }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        distance_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(
+            device,
+            &self.pipeline,
+            &[(0, distance_buffer), (1, params_buffer)],
+        )
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+    }
+}
+
+impl Kernel for IncrementalKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [16, 16, 1]
+    }
+}
+
+pub struct IncrementalUpdate {
+    kernel: IncrementalKernel,
+}
+
+impl IncrementalUpdate {
+    pub fn new(device: &wgpu::Device) -> IncrementalUpdate {
+        let kernel = IncrementalKernel::new(device);
+        IncrementalUpdate { kernel }
+    }
+
+    // Applies inserted edges or decreased weights `(u, v, w)` to an
+    // undirected distance matrix in place, at O(n^2) work per edge.
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+        edges: &[(usize, usize, f32)],
+    ) -> Result<(), String> {
+        validate(edges, n)?;
+        let size = std::mem::size_of::<IncrementalParams>() as u64;
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.kernel.bind(device, distance_buffer, &params_buffer);
+
+        let mut params_buffer_staging = wgpu::util::StagingBelt::new(size);
+        for &(u, v, w) in edges {
+            for (u, v) in [(u, v), (v, u)] {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                let params = IncrementalParams {
+                    n: n as u32,
+                    stride: stride as u32,
+                    u: u as u32,
+                    v: v as u32,
+                    w,
+                    padding: [0; 3],
+                };
+                params_buffer_staging
+                    .write_buffer(
+                        &mut encoder,
+                        &params_buffer,
+                        0,
+                        std::num::NonZeroU64::new(size).unwrap(),
+                        device,
+                    )
+                    .copy_from_slice(bytemuck::bytes_of(&params));
+                params_buffer_staging.finish();
+                self.kernel.run(&mut encoder, &bind_group, n);
+                queue.submit(Some(encoder.finish()));
+                params_buffer_staging.recall();
+            }
+        }
+        Ok(())
+    }
+}
+
+// Device state needed for decremental updates, laid out like the
+// `WarshallFloyd` buffers. `weight` holds the current adjacency matrix.
+pub struct DynamicBuffers {
+    pub weight: wgpu::Buffer,
+    pub distance: wgpu::Buffer,
+    pub predecessor: wgpu::Buffer,
+    scratch: wgpu::Buffer,
+}
+
+impl DynamicBuffers {
+    pub fn new(device: &wgpu::Device, n: usize) -> DynamicBuffers {
+        let size = WarshallFloyd::<f32>::buffer_size(n) as u64;
+        let create_buffer = |usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        DynamicBuffers {
+            weight: create_buffer(
+                wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            ),
+            distance: create_buffer(
+                wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            ),
+            predecessor: create_buffer(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC),
+            scratch: create_buffer(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST),
+        }
+    }
+
+    pub fn stage_adjacency_matrix(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        edges: &[(usize, usize)],
+        n: usize,
+    ) {
+        stage_adjacency_matrix(device, queue, edges, n, &self.weight);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecrementalReport {
+    pub affected_pairs: usize,
+    pub affected_rows: usize,
+    pub recomputed: bool,
+}
+
+pub struct DecrementalKernel {
+    predecessors_pipeline: wgpu::ComputePipeline,
+    mark_pipeline: wgpu::ComputePipeline,
+    relax_pipeline: wgpu::ComputePipeline,
+    finish_pipeline: wgpu::ComputePipeline,
+}
+
+impl DecrementalKernel {
+    pub fn new(device: &wgpu::Device) -> DecrementalKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("decremental.wgsl"));
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                module: &module,
+                layout: None,
+                entry_point,
+            })
+        };
+        DecrementalKernel {
+            predecessors_pipeline: create_pipeline("predecessors"),
+            mark_pipeline: create_pipeline("mark"),
+            relax_pipeline: create_pipeline("relax"),
+            finish_pipeline: create_pipeline("finish"),
+        }
+    }
+
+    fn run(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+        n: usize,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+    }
+}
+
+impl Kernel for DecrementalKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [16, 16, 1]
+    }
+}
+
+pub struct DecrementalUpdate {
+    kernel: DecrementalKernel,
+    warshall_floyd: WarshallFloyd,
+    threshold: f32,
+}
+
+impl DecrementalUpdate {
+    // Falls back to a full recomputation once more than `threshold` of the
+    // n^2 pairs are affected by an update.
+    pub fn new(device: &wgpu::Device, threshold: f32) -> DecrementalUpdate {
+        DecrementalUpdate {
+            kernel: DecrementalKernel::new(device),
+            warshall_floyd: WarshallFloyd::new(device),
+            threshold,
+        }
+    }
+
+    // Rebuilds `distance` and `predecessor` from `weight`.
+    pub fn recompute(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffers: &DynamicBuffers,
+        n: usize,
+    ) {
+        let size = WarshallFloyd::<f32>::buffer_size(n) as u64;
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&buffers.weight, 0, &buffers.scratch, 0, size);
+        queue.submit(Some(encoder.finish()));
+        self.warshall_floyd
+            .run(device, queue, &buffers.scratch, &buffers.distance, n);
+
+        let params_buffer = self.params_buffer(device, queue, n, 0);
+        let bind_group = create_bind_group(
+            device,
+            &self.kernel.predecessors_pipeline,
+            &[
+                (0, &buffers.distance),
+                (1, &buffers.weight),
+                (2, &buffers.predecessor),
+                (5, &params_buffer),
+            ],
+        );
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.kernel.run(
+            &mut encoder,
+            &self.kernel.predecessors_pipeline,
+            &bind_group,
+            n,
+        );
+        queue.submit(Some(encoder.finish()));
+    }
+
+    // Removes edges or raises their weights; `(u, v, f32::INFINITY)` deletes
+    // the undirected edge `{u, v}`.
+    pub async fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffers: &DynamicBuffers,
+        n: usize,
+        edges: &[(usize, usize, f32)],
+    ) -> Result<DecrementalReport, String> {
+        validate(edges, n)?;
+        let stride = WarshallFloydKernel::stride_x(n);
+        let mut report = DecrementalReport {
+            affected_pairs: 0,
+            affected_rows: 0,
+            recomputed: false,
+        };
+        if edges.is_empty() {
+            return Ok(report);
+        }
+        for &(u, v, w) in edges {
+            for (u, v) in [(u, v), (v, u)] {
+                queue.write_buffer(
+                    &buffers.weight,
+                    ((u * stride + v) * 4) as u64,
+                    bytemuck::bytes_of(&w),
+                );
+            }
+        }
+
+        // The first n counters hold the affected pairs per row, the last one
+        // the number of pairs improved by a relaxation sweep.
+        let counter_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: ((n + 1) * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let removed_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (edges.len() * 8) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let removed = edges
+            .iter()
+            .map(|&(u, v, _)| [u as u32, v as u32])
+            .collect::<Vec<_>>();
+        queue.write_buffer(&removed_buffer, 0, bytemuck::cast_slice(&removed));
+        let params_buffer = self.params_buffer(device, queue, n, edges.len());
+
+        let bind_group = create_bind_group(
+            device,
+            &self.kernel.mark_pipeline,
+            &[
+                (0, &buffers.distance),
+                (2, &buffers.predecessor),
+                (3, &counter_buffer),
+                (4, &removed_buffer),
+                (5, &params_buffer),
+            ],
+        );
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.kernel
+            .run(&mut encoder, &self.kernel.mark_pipeline, &bind_group, n);
+        queue.submit(Some(encoder.finish()));
+        let counter = download_buffer::<u32>(device, queue, &counter_buffer)
+            .await
+            .unwrap();
+        report.affected_pairs = counter[..n].iter().map(|&c| c as usize).sum();
+        report.affected_rows = counter[..n].iter().filter(|&&c| c > 0).count();
+        if report.affected_pairs as f32 > self.threshold * (n * n) as f32 {
+            self.recompute(device, queue, buffers, n);
+            report.recomputed = true;
+            return Ok(report);
+        }
+        if report.affected_pairs == 0 {
+            return Ok(report);
+        }
+
+        let bind_group = create_bind_group(
+            device,
+            &self.kernel.relax_pipeline,
+            &[
+                (0, &buffers.distance),
+                (1, &buffers.weight),
+                (2, &buffers.predecessor),
+                (3, &counter_buffer),
+                (5, &params_buffer),
+            ],
+        );
+        loop {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            encoder.clear_buffer(&counter_buffer, (n * 4) as u64, None);
+            self.kernel
+                .run(&mut encoder, &self.kernel.relax_pipeline, &bind_group, n);
+            queue.submit(Some(encoder.finish()));
+            let counter = download_buffer::<u32>(device, queue, &counter_buffer)
+                .await
+                .unwrap();
+            if counter[n] == 0 {
+                break;
+            }
+        }
+
+        let bind_group = create_bind_group(
+            device,
+            &self.kernel.finish_pipeline,
+            &[
+                (0, &buffers.distance),
+                (2, &buffers.predecessor),
+                (5, &params_buffer),
+            ],
+        );
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.kernel
+            .run(&mut encoder, &self.kernel.finish_pipeline, &bind_group, n);
+        queue.submit(Some(encoder.finish()));
+        Ok(report)
+    }
+
+    fn params_buffer(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        n: usize,
+        num_removed: usize,
+    ) -> wgpu::Buffer {
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params = vec![
+            n as u32,
+            WarshallFloydKernel::stride_x(n) as u32,
+            num_removed as u32,
+            0,
+        ];
+        queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+        params_buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::download_distance_matrix,
+        testing::{init, path_graph, warshall_floyd},
+        warshall_floyd::WarshallFloydKernel,
+    };
+
+    #[tokio::test]
+    async fn test_incremental_update() {
+        let (device, queue) = init().await;
+        let n = 30;
+        let mut edges = path_graph(n);
+        let distance_buffer = warshall_floyd(&device, &queue, &edges, n);
+        let inserted = [(0, 29), (10, 20), (5, 25)];
+        IncrementalUpdate::new(&device)
+            .run(
+                &device,
+                &queue,
+                &distance_buffer,
+                n,
+                WarshallFloydKernel::stride_x(n),
+                &inserted.map(|(u, v)| (u, v, 1.)),
+            )
+            .unwrap();
+        let result = download_distance_matrix(&device, &queue, &distance_buffer)
+            .await
+            .unwrap();
+
+        edges.extend(inserted);
+        let expected_buffer = warshall_floyd(&device, &queue, &edges, n);
+        let expected = download_distance_matrix(&device, &queue, &expected_buffer)
+            .await
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_update_invalid() {
+        let (device, queue) = init().await;
+        let n = 4;
+        let distance_buffer = warshall_floyd(&device, &queue, &path_graph(n), n);
+        let update = IncrementalUpdate::new(&device);
+        let stride = WarshallFloydKernel::stride_x(n);
+        for (edge, message) in [
+            ((0, 4, 1.), "edge (0, 4) out of range"),
+            ((0, 2, -1.), "invalid weight -1"),
+            ((0, 2, f32::NAN), "invalid weight NaN"),
+        ] {
+            assert_eq!(
+                update.run(&device, &queue, &distance_buffer, n, stride, &[edge]),
+                Err(message.to_string())
+            );
+        }
+        let result = download_distance_matrix(&device, &queue, &distance_buffer)
+            .await
+            .unwrap();
+        assert_eq!(result[2], 2.);
+    }
+
+    async fn test_decremental_update(threshold: f32) -> DecrementalReport {
+        let (device, queue) = init().await;
+        let n = 20;
+        let mut edges = (0..n).map(|i| (i, (i + 1) % n)).collect::<Vec<_>>();
+        edges.push((0, 10));
+        let buffers = DynamicBuffers::new(&device, n);
+        buffers.stage_adjacency_matrix(&device, &queue, &edges, n);
+        let update = DecrementalUpdate::new(&device, threshold);
+        update.recompute(&device, &queue, &buffers, n);
+        let report = update
+            .run(
+                &device,
+                &queue,
+                &buffers,
+                n,
+                &[(3, 4, f32::INFINITY), (12, 13, 2.)],
+            )
+            .await
+            .unwrap();
+        let result = download_distance_matrix(&device, &queue, &buffers.distance)
+            .await
+            .unwrap();
+
+        edges.retain(|&e| e != (3, 4));
+        let expected_buffers = DynamicBuffers::new(&device, n);
+        expected_buffers.stage_adjacency_matrix(&device, &queue, &edges, n);
+        queue.write_buffer(
+            &expected_buffers.weight,
+            ((12 * WarshallFloydKernel::stride_x(n) + 13) * 4) as u64,
+            bytemuck::bytes_of(&2f32),
+        );
+        queue.write_buffer(
+            &expected_buffers.weight,
+            ((13 * WarshallFloydKernel::stride_x(n) + 12) * 4) as u64,
+            bytemuck::bytes_of(&2f32),
+        );
+        update.recompute(&device, &queue, &expected_buffers, n);
+        let expected = download_distance_matrix(&device, &queue, &expected_buffers.distance)
+            .await
+            .unwrap();
+        assert_eq!(result, expected);
+        report
+    }
+
+    #[tokio::test]
+    async fn test_decremental_update_rows() {
+        let report = test_decremental_update(1.).await;
+        assert!(report.affected_pairs > 0);
+        assert!(report.affected_rows > 0);
+        assert!(!report.recomputed);
+    }
+
+    #[tokio::test]
+    async fn test_decremental_update_fallback() {
+        let report = test_decremental_update(0.).await;
+        assert!(report.recomputed);
+    }
+
+    #[tokio::test]
+    async fn test_decremental_update_invalid() {
+        let (device, queue) = init().await;
+        let n = 4;
+        let buffers = DynamicBuffers::new(&device, n);
+        buffers.stage_adjacency_matrix(&device, &queue, &path_graph(n), n);
+        let update = DecrementalUpdate::new(&device, 1.);
+        update.recompute(&device, &queue, &buffers, n);
+        for (edge, message) in [
+            ((4, 0, f32::INFINITY), "edge (4, 0) out of range"),
+            ((0, 1, -1.), "invalid weight -1"),
+            ((0, 1, f32::NAN), "invalid weight NaN"),
+        ] {
+            assert_eq!(
+                update.run(&device, &queue, &buffers, n, &[edge]).await,
+                Err(message.to_string())
+            );
+        }
+        let result = download_distance_matrix(&device, &queue, &buffers.distance)
+            .await
+            .unwrap();
+        assert_eq!(result[1], 1.);
+    }
+}
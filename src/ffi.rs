@@ -0,0 +1,337 @@
+// C API, built with the `capi` feature. The header is generated with
+// `cbindgen` into `include/wgpu_test.h`; `tests/capi.rs` checks that it is up
+// to date. Pointers must be valid for the lengths passed along with them, and
+// a context must not be used from two threads at once.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::{
+    apsp::{self, ApspError},
+    cpu,
+    graph::Graph,
+    matrix::DistanceMatrix,
+};
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApspStatus {
+    Ok = 0,
+    InvalidArgument = 1,
+    NoAdapter = 2,
+    DeviceError = 3,
+    TooLarge = 4,
+    NotStaged = 5,
+    NotRun = 6,
+    BufferTooSmall = 7,
+    Internal = 8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ApspAlgorithm {
+    WarshallFloyd = 0,
+    TropicalNaive = 1,
+    TropicalBlock = 2,
+    Bfs = 3,
+    Cpu = 4,
+}
+
+impl TryFrom<u32> for ApspStatus {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<ApspStatus, u32> {
+        Ok(match value {
+            0 => ApspStatus::Ok,
+            1 => ApspStatus::InvalidArgument,
+            2 => ApspStatus::NoAdapter,
+            3 => ApspStatus::DeviceError,
+            4 => ApspStatus::TooLarge,
+            5 => ApspStatus::NotStaged,
+            6 => ApspStatus::NotRun,
+            7 => ApspStatus::BufferTooSmall,
+            8 => ApspStatus::Internal,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl TryFrom<u32> for ApspAlgorithm {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<ApspAlgorithm, u32> {
+        Ok(match value {
+            0 => ApspAlgorithm::WarshallFloyd,
+            1 => ApspAlgorithm::TropicalNaive,
+            2 => ApspAlgorithm::TropicalBlock,
+            3 => ApspAlgorithm::Bfs,
+            4 => ApspAlgorithm::Cpu,
+            _ => return Err(value),
+        })
+    }
+}
+
+// Opaque to C. Owns the device, the staged graph and the last result.
+pub struct ApspContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    graph: Option<Graph>,
+    distance: Option<DistanceMatrix>,
+    error: CString,
+}
+
+struct Error(ApspStatus, String);
+
+impl ApspContext {
+    fn new() -> Result<ApspContext, Error> {
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(backends);
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or_else(|| {
+                Error(
+                    ApspStatus::NoAdapter,
+                    "no suitable adapter found".to_string(),
+                )
+            })?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: adapter.limits(),
+            },
+            None,
+        ))
+        .map_err(|e| Error(ApspStatus::DeviceError, e.to_string()))?;
+        Ok(ApspContext {
+            device,
+            queue,
+            graph: None,
+            distance: None,
+            error: CString::default(),
+        })
+    }
+
+    fn run(&mut self, algorithm: ApspAlgorithm) -> Result<(), Error> {
+        let graph = self.graph.as_ref().ok_or_else(|| {
+            Error(
+                ApspStatus::NotStaged,
+                "no graph has been staged".to_string(),
+            )
+        })?;
+        let n = graph.n;
+        let (device, queue) = (&self.device, &self.queue);
+        // The GPU algorithms need at least one vertex.
+        if n == 0 {
+            self.distance = Some(DistanceMatrix::new(0, vec![]));
+            return Ok(());
+        }
+        let algorithm = match algorithm {
+            ApspAlgorithm::Cpu => {
+                self.distance = Some(DistanceMatrix::new(n, cpu::warshall_floyd_graph(graph)));
+                return Ok(());
+            }
+            ApspAlgorithm::WarshallFloyd => apsp::Algorithm::WarshallFloyd,
+            ApspAlgorithm::TropicalNaive => apsp::Algorithm::TropicalNaive,
+            ApspAlgorithm::TropicalBlock => apsp::Algorithm::TropicalBlock,
+            ApspAlgorithm::Bfs => apsp::Algorithm::Bfs,
+        };
+        let (buffer, stride) = pollster::block_on(apsp::run(device, queue, graph, algorithm))
+            .map_err(|e| {
+                let status = match e {
+                    ApspError::TooLarge { .. } => ApspStatus::TooLarge,
+                    ApspError::Empty | ApspError::Unsupported(_) => ApspStatus::InvalidArgument,
+                };
+                Error(status, e.to_string())
+            })?;
+        let distance =
+            pollster::block_on(DistanceMatrix::download(device, queue, &buffer, n, stride))
+                .ok_or_else(|| {
+                    Error(
+                        ApspStatus::DeviceError,
+                        "failed to download the distance matrix".to_string(),
+                    )
+                })?;
+        self.distance = Some(distance);
+        Ok(())
+    }
+}
+
+// Runs `f`, recording its error message in the context. Panics, e.g. wgpu
+// validation errors, must not unwind into C.
+unsafe fn call<F>(context: *mut ApspContext, f: F) -> ApspStatus
+where
+    F: FnOnce(&mut ApspContext) -> Result<(), Error>,
+{
+    let context = match context.as_mut() {
+        Some(context) => context,
+        None => return ApspStatus::InvalidArgument,
+    };
+    let result = catch_unwind(AssertUnwindSafe(|| f(context)))
+        .unwrap_or_else(|_| Err(Error(ApspStatus::Internal, "internal error".to_string())));
+    match result {
+        Ok(()) => {
+            context.error = CString::default();
+            ApspStatus::Ok
+        }
+        Err(Error(status, message)) => {
+            context.error = CString::new(message).unwrap_or_default();
+            status
+        }
+    }
+}
+
+// Stores the new context in `*context`, or null on failure. Picks the adapter
+// from the `WGPU_BACKEND` environment variable if set.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_context_create(context: *mut *mut ApspContext) -> ApspStatus {
+    if context.is_null() {
+        return ApspStatus::InvalidArgument;
+    }
+    *context = ptr::null_mut();
+    match catch_unwind(ApspContext::new) {
+        Ok(Ok(c)) => {
+            *context = Box::into_raw(Box::new(c));
+            ApspStatus::Ok
+        }
+        Ok(Err(Error(status, _))) => status,
+        Err(_) => ApspStatus::Internal,
+    }
+}
+
+// Accepts null.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_context_destroy(context: *mut ApspContext) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+// `edges` holds `2 * m` vertex indices, `weights` is either null or holds `m`
+// non-negative weights. Replaces the previously staged graph and discards the
+// last result.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_stage_edges(
+    context: *mut ApspContext,
+    n: usize,
+    edges: *const u32,
+    weights: *const f32,
+    m: usize,
+    directed: bool,
+) -> ApspStatus {
+    call(context, |context| {
+        if edges.is_null() && m > 0 {
+            return Err(Error(
+                ApspStatus::InvalidArgument,
+                "edges is null".to_string(),
+            ));
+        }
+        let endpoints = if m > 0 {
+            slice::from_raw_parts(edges, 2 * m)
+        } else {
+            &[]
+        };
+        let graph = Graph {
+            n,
+            edges: endpoints
+                .chunks_exact(2)
+                .map(|e| (e[0] as usize, e[1] as usize))
+                .collect(),
+            weights: (!weights.is_null()).then(|| slice::from_raw_parts(weights, m).to_vec()),
+            directed,
+            labels: None,
+        };
+        graph
+            .validate()
+            .map_err(|message| Error(ApspStatus::InvalidArgument, message))?;
+        context.graph = Some(graph);
+        context.distance = None;
+        Ok(())
+    })
+}
+
+// `algorithm` is one of `ApspAlgorithm`; it is taken as an integer because
+// any other value would be undefined behaviour in a C enum parameter.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_run(context: *mut ApspContext, algorithm: u32) -> ApspStatus {
+    call(context, |context| {
+        let algorithm = ApspAlgorithm::try_from(algorithm).map_err(|value| {
+            Error(
+                ApspStatus::InvalidArgument,
+                format!("unknown algorithm {}", value),
+            )
+        })?;
+        context.run(algorithm)
+    })
+}
+
+// Number of vertices of the staged graph, 0 if there is none.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_vertex_count(context: *const ApspContext) -> usize {
+    context
+        .as_ref()
+        .and_then(|context| context.graph.as_ref())
+        .map_or(0, |graph| graph.n)
+}
+
+// Copies the `n * n` row-major distances of the last run into `dst`, which
+// holds `len` floats. Unreachable pairs are `INFINITY`.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_copy_distances(
+    context: *mut ApspContext,
+    dst: *mut f32,
+    len: usize,
+) -> ApspStatus {
+    call(context, |context| {
+        let distance = context
+            .distance
+            .as_ref()
+            .ok_or_else(|| Error(ApspStatus::NotRun, "apsp_run has not succeeded".to_string()))?;
+        let src = distance.as_slice();
+        if dst.is_null() || len < src.len() {
+            return Err(Error(
+                ApspStatus::BufferTooSmall,
+                format!("expected a buffer of {} floats", src.len()),
+            ));
+        }
+        ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len());
+        Ok(())
+    })
+}
+
+// Message of the last failed call on `context`, empty after a successful one.
+// Valid until the next call on `context`.
+#[no_mangle]
+pub unsafe extern "C" fn apsp_last_error(context: *const ApspContext) -> *const c_char {
+    match context.as_ref() {
+        Some(context) => context.error.as_ptr(),
+        None => c"invalid context".as_ptr(),
+    }
+}
+
+// `status` is one of `ApspStatus`, taken as an integer like the algorithm of
+// `apsp_run`.
+#[no_mangle]
+pub extern "C" fn apsp_status_string(status: u32) -> *const c_char {
+    let status = match ApspStatus::try_from(status) {
+        Ok(status) => status,
+        Err(_) => return c"unknown status".as_ptr(),
+    };
+    let message: &CStr = match status {
+        ApspStatus::Ok => c"ok",
+        ApspStatus::InvalidArgument => c"invalid argument",
+        ApspStatus::NoAdapter => c"no suitable adapter found",
+        ApspStatus::DeviceError => c"device error",
+        ApspStatus::TooLarge => c"graph too large for the device",
+        ApspStatus::NotStaged => c"no graph staged",
+        ApspStatus::NotRun => c"no distances computed",
+        ApspStatus::BufferTooSmall => c"buffer too small",
+        ApspStatus::Internal => c"internal error",
+    };
+    message.as_ptr()
+}
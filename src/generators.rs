@@ -0,0 +1,186 @@
+// Seeded random graph families for tests and benchmarks. All graphs are
+// undirected and free of self-loops and parallel edges; only
+// `random_geometric` is weighted.
+use crate::graph::Graph;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashSet;
+
+// G(n, p): every pair is an edge with probability `p`.
+pub fn erdos_renyi(n: usize, p: f64, seed: u64) -> Graph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let edges = (0..n)
+        .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+        .filter(|_| rng.gen_bool(p))
+        .collect();
+    Graph::new(n, edges)
+}
+
+// Preferential attachment: starting from `m` isolated vertices, every new
+// vertex connects to `m` distinct vertices chosen with probability
+// proportional to their degree.
+pub fn barabasi_albert(n: usize, m: usize, seed: u64) -> Graph {
+    assert!(m >= 1 && m < n, "expected 1 <= m < n");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut edges = Vec::with_capacity((n - m) * m);
+    // Every vertex appears once per incident edge.
+    let mut endpoints = Vec::with_capacity(2 * (n - m) * m);
+    let mut targets = (0..m).collect::<Vec<_>>();
+    for v in m..n {
+        for &u in &targets {
+            edges.push((u, v));
+            endpoints.extend([u, v]);
+        }
+        let mut chosen = HashSet::with_capacity(m);
+        while chosen.len() < m {
+            chosen.insert(endpoints[rng.gen_range(0..endpoints.len())]);
+        }
+        targets = chosen.into_iter().collect();
+        targets.sort_unstable();
+    }
+    Graph::new(n, edges)
+}
+
+// `rows * cols` lattice with 4-neighbourhoods. Vertex `(i, j)` is
+// `i * cols + j`.
+pub fn grid(rows: usize, cols: usize) -> Graph {
+    let mut edges = Vec::new();
+    for i in 0..rows {
+        for j in 0..cols {
+            let v = i * cols + j;
+            if j + 1 < cols {
+                edges.push((v, v + 1));
+            }
+            if i + 1 < rows {
+                edges.push((v, v + cols));
+            }
+        }
+    }
+    Graph::new(rows * cols, edges)
+}
+
+// Points uniform in the unit square, connected if at most `radius` apart.
+// Edges are weighted by the Euclidean distance.
+pub fn random_geometric(n: usize, radius: f32, seed: u64) -> Graph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let points = (0..n)
+        .map(|_| (rng.gen::<f32>(), rng.gen::<f32>()))
+        .collect::<Vec<_>>();
+    let mut edges = Vec::new();
+    let mut weights = Vec::new();
+    for u in 0..n {
+        for v in u + 1..n {
+            let d = (points[u].0 - points[v].0).hypot(points[u].1 - points[v].1);
+            if d <= radius {
+                edges.push((u, v));
+                weights.push(d);
+            }
+        }
+    }
+    Graph {
+        weights: Some(weights),
+        ..Graph::new(n, edges)
+    }
+}
+
+// Ring where every vertex is connected to its `k / 2` nearest neighbours on
+// each side, after which every edge `(u, v)` is rewired to `(u, w)` with
+// probability `p`, `w` chosen uniformly among the vertices not yet adjacent
+// to `u`.
+pub fn watts_strogatz(n: usize, k: usize, p: f64, seed: u64) -> Graph {
+    assert!(k.is_multiple_of(2) && k < n, "expected an even k < n");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let key = |u: usize, v: usize| (u.min(v), u.max(v));
+    let mut edges = (1..=k / 2)
+        .flat_map(|d| (0..n).map(move |u| (u, (u + d) % n)))
+        .collect::<Vec<_>>();
+    let mut present = edges
+        .iter()
+        .map(|&(u, v)| key(u, v))
+        .collect::<HashSet<_>>();
+    let mut degree = vec![k; n];
+    for edge in edges.iter_mut() {
+        let (u, v) = *edge;
+        if degree[u] == n - 1 || !rng.gen_bool(p) {
+            continue;
+        }
+        let w = loop {
+            let w = rng.gen_range(0..n);
+            if w != u && !present.contains(&key(u, w)) {
+                break w;
+            }
+        };
+        present.remove(&key(u, v));
+        present.insert(key(u, w));
+        degree[v] -= 1;
+        degree[w] += 1;
+        *edge = (u, w);
+    }
+    Graph::new(n, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu, distance::Distance};
+
+    fn assert_simple(graph: &Graph) {
+        let mut seen = HashSet::new();
+        for &(u, v) in &graph.edges {
+            assert_ne!(u, v);
+            assert!(seen.insert((u.min(v), u.max(v))), "parallel edge");
+        }
+    }
+
+    #[test]
+    fn test_seeded() {
+        let graphs = |seed| {
+            vec![
+                erdos_renyi(100, 0.05, seed),
+                barabasi_albert(100, 3, seed),
+                random_geometric(100, 0.2, seed),
+                watts_strogatz(100, 6, 0.2, seed),
+            ]
+        };
+        assert_eq!(graphs(7), graphs(7));
+        assert_ne!(graphs(7), graphs(8));
+        for graph in graphs(7) {
+            assert_eq!(graph.n, 100);
+            assert!(!graph.directed);
+            assert_simple(&graph);
+        }
+    }
+
+    #[test]
+    fn test_edge_counts() {
+        assert_eq!(erdos_renyi(30, 1., 0).edges.len(), 30 * 29 / 2);
+        assert!(erdos_renyi(30, 0., 0).edges.is_empty());
+        assert_eq!(barabasi_albert(50, 4, 0).edges.len(), 4 * 46);
+        assert_eq!(watts_strogatz(50, 4, 0.5, 0).edges.len(), 50 * 2);
+        assert_eq!(grid(3, 5).edges.len(), 3 * 4 + 2 * 5);
+
+        let graph = random_geometric(50, 2., 0);
+        assert_eq!(graph.edges.len(), 50 * 49 / 2);
+        assert_eq!(graph.weights.unwrap().len(), graph.edges.len());
+    }
+
+    #[test]
+    fn test_distances() {
+        let distance = cpu::warshall_floyd_graph::<u32>(&grid(4, 6));
+        for u in 0..24usize {
+            for v in 0..24usize {
+                let expected = (u / 6).abs_diff(v / 6) + (u % 6).abs_diff(v % 6);
+                assert_eq!(distance[u * 24 + v], expected as u32);
+            }
+        }
+
+        // Without rewiring the ring distance is `ceil(d / (k / 2))`.
+        let distance = cpu::warshall_floyd_graph::<u32>(&watts_strogatz(20, 4, 0., 0));
+        for (v, &d) in distance.iter().take(20).enumerate() {
+            assert_eq!(d, v.min(20 - v).div_ceil(2) as u32);
+        }
+
+        // Preferential attachment keeps the graph connected.
+        let distance = cpu::warshall_floyd_graph::<u32>(&barabasi_albert(60, 2, 3));
+        assert!(distance.iter().all(|&d| d != u32::INFINITY));
+    }
+}
@@ -0,0 +1,212 @@
+// Graph on the vertices `0..n`. `weights`, if present, has one entry per
+// edge; `labels`, if present, one per vertex.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedGraph"))]
+pub struct Graph {
+    pub n: usize,
+    pub edges: Vec<(usize, usize)>,
+    pub weights: Option<Vec<f32>>,
+    pub directed: bool,
+    pub labels: Option<Vec<String>>,
+}
+
+impl Graph {
+    // Undirected and unweighted.
+    pub fn new(n: usize, edges: Vec<(usize, usize)>) -> Graph {
+        assert!(
+            edges.iter().all(|&(u, v)| u < n && v < n),
+            "edge endpoint out of range"
+        );
+        Graph {
+            n,
+            edges,
+            ..Graph::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(&(u, v)) = self
+            .edges
+            .iter()
+            .find(|&&(u, v)| u >= self.n || v >= self.n)
+        {
+            return Err(format!("edge ({}, {}) out of range", u, v));
+        }
+        if self
+            .weights
+            .as_ref()
+            .is_some_and(|w| w.len() != self.edges.len())
+        {
+            return Err("expected one weight per edge".to_string());
+        }
+        if let Some(&w) = self
+            .weights
+            .iter()
+            .flatten()
+            .find(|&&w| w.is_nan() || w < 0.)
+        {
+            return Err(format!("invalid weight {}", w));
+        }
+        if self.labels.as_ref().is_some_and(|l| l.len() != self.n) {
+            return Err("expected one label per vertex".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Deserialized as is, then validated, so that a malformed graph is rejected
+// instead of panicking in a kernel.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct UncheckedGraph {
+    n: usize,
+    edges: Vec<(usize, usize)>,
+    weights: Option<Vec<f32>>,
+    directed: bool,
+    labels: Option<Vec<String>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedGraph> for Graph {
+    type Error = String;
+
+    fn try_from(graph: UncheckedGraph) -> Result<Graph, String> {
+        let graph = Graph {
+            n: graph.n,
+            edges: graph.edges,
+            weights: graph.weights,
+            directed: graph.directed,
+            labels: graph.labels,
+        };
+        graph.validate()?;
+        Ok(graph)
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl Graph {
+    // Vertex `i` is `graph.from_index(i)`, so the distances can be looked up
+    // by node with `DistanceMatrix::node_distance`. Indices of removed nodes
+    // of a `StableGraph` become isolated vertices.
+    pub fn from_petgraph<G, F>(graph: G, mut weight: F) -> Graph
+    where
+        G: petgraph::visit::IntoEdgeReferences
+            + petgraph::visit::NodeIndexable
+            + petgraph::visit::GraphProp,
+        F: FnMut(G::EdgeRef) -> f32,
+    {
+        use petgraph::visit::EdgeRef;
+
+        let (edges, weights) = graph
+            .edge_references()
+            .map(|e| {
+                (
+                    (graph.to_index(e.source()), graph.to_index(e.target())),
+                    weight(e),
+                )
+            })
+            .unzip();
+        Graph {
+            n: graph.node_bound(),
+            edges,
+            weights: Some(weights),
+            directed: graph.is_directed(),
+            labels: None,
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "petgraph", feature = "serde")))]
+mod tests {
+    use super::*;
+    #[cfg(feature = "petgraph")]
+    use crate::{cpu, matrix::DistanceMatrix};
+    #[cfg(feature = "petgraph")]
+    use petgraph::{
+        graph::{DiGraph, UnGraph},
+        stable_graph::StableGraph,
+    };
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_from_petgraph() {
+        let mut graph = DiGraph::<&str, f32>::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        graph.add_edge(a, b, 2.);
+        graph.add_edge(b, c, 3.);
+        graph.add_edge(c, a, 1.);
+        let input = Graph::from_petgraph(&graph, |e| *e.weight());
+        assert!(input.directed);
+        let distance = DistanceMatrix::new(3, cpu::warshall_floyd_graph::<f32>(&input));
+        assert_eq!(distance.node_distance(&graph, a, c), 5.);
+        assert_eq!(distance.node_distance(&graph, c, b), 3.);
+        assert_eq!(
+            distance.node_row(&graph, b).collect::<Vec<_>>(),
+            vec![(a, 4.), (b, 0.), (c, 3.)]
+        );
+
+        let graph = UnGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+        let input = Graph::from_petgraph(&graph, |_| 1.);
+        assert!(!input.directed);
+        let distance = DistanceMatrix::new(3, cpu::warshall_floyd_graph::<u32>(&input));
+        assert_eq!(distance.node_distance(&graph, 2.into(), 0.into()), 2);
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_from_stable_graph() {
+        let mut graph = StableGraph::<(), u32>::new();
+        let nodes = (0..4).map(|_| graph.add_node(())).collect::<Vec<_>>();
+        graph.add_edge(nodes[0], nodes[1], 1);
+        graph.add_edge(nodes[1], nodes[3], 4);
+        graph.remove_node(nodes[2]);
+        let input = Graph::from_petgraph(&graph, |e| *e.weight() as f32);
+        assert_eq!(input.n, 4);
+        let distance = DistanceMatrix::new(4, cpu::warshall_floyd_graph::<f32>(&input));
+        assert_eq!(
+            distance.node_row(&graph, nodes[0]).collect::<Vec<_>>(),
+            vec![(nodes[0], 0.), (nodes[1], 1.), (nodes[3], 5.)]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json() {
+        let graph = Graph {
+            n: 3,
+            edges: vec![(0, 1), (1, 2)],
+            weights: Some(vec![0.5, 2.]),
+            directed: true,
+            labels: None,
+        };
+        let json = serde_json::to_string(&graph).unwrap();
+        assert_eq!(
+            json,
+            r#"{"n":3,"edges":[[0,1],[1,2]],"weights":[0.5,2.0],"directed":true,"labels":null}"#
+        );
+        assert_eq!(serde_json::from_str::<Graph>(&json).unwrap(), graph);
+        assert_eq!(
+            serde_json::from_str::<Graph>(r#"{"n":2,"edges":[[0,1]]}"#).unwrap(),
+            Graph::new(2, vec![(0, 1)])
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_invalid() {
+        let error = serde_json::from_str::<Graph>(r#"{"n":2,"edges":[[0,2]]}"#).unwrap_err();
+        assert!(error.to_string().contains("edge (0, 2) out of range"));
+        let error = serde_json::from_str::<Graph>(r#"{"n":2,"edges":[[0,1]],"weights":[1.0,2.0]}"#)
+            .unwrap_err();
+        assert!(error.to_string().contains("one weight per edge"));
+        let error = serde_json::from_str::<Graph>(r#"{"n":2,"edges":[[0,1]],"weights":[-1.0]}"#)
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid weight -1"));
+        let error = serde_json::from_str::<Graph>(r#"{"n":2,"labels":["a"]}"#).unwrap_err();
+        assert!(error.to_string().contains("one label per vertex"));
+    }
+}
@@ -0,0 +1,31 @@
+struct Params {
+  n : u32,
+  stride : u32,
+  u : u32,
+  v : u32,
+  w : f32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read_write> distance: array<f32>;
+@group(0)
+@binding(1)
+var<uniform> params: Params;
+
+// Relaxes every pair through the arc u -> v in place. With non-negative
+// weights neither d[y][u] nor d[v][x] can change in the same pass.
+@compute
+@workgroup_size(16, 16)
+fn incremental(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (x < n && y < n) {
+    var d : f32 = distance[y * stride + params.u] + params.w + distance[params.v * stride + x];
+    if (d < distance[y * stride + x]) {
+      distance[y * stride + x] = d;
+    }
+  }
+}
@@ -0,0 +1,885 @@
+use crate::{distance::Distance, graph::Graph, matrix::DistanceMatrix};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead, Read, Write},
+    time::Duration,
+};
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    // `line` is 1-based.
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+fn parse_error<T>(line: usize, message: impl Into<String>) -> Result<T, ReadError> {
+    Err(ReadError::Parse {
+        line,
+        message: message.into(),
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Indexing {
+    #[default]
+    ZeroBased,
+    OneBased,
+    // Arbitrary tokens, numbered in order of first appearance and kept in
+    // `Graph::labels`.
+    Labels,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EdgeListOptions {
+    pub indexing: Indexing,
+    pub directed: bool,
+}
+
+// Reads lines of `source target [weight]`. Fields are separated by
+// whitespace; blank lines and everything after `#` or `%` are ignored. Lines
+// without a weight get weight 1 if any other line has one.
+pub fn read_edge_list<R: BufRead>(
+    reader: R,
+    options: &EdgeListOptions,
+) -> Result<Graph, ReadError> {
+    let mut n = 0;
+    let mut edges = vec![];
+    let mut weights = vec![];
+    let mut weighted = false;
+    let mut labels = vec![];
+    let mut ids = HashMap::<String, usize>::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = i + 1;
+        let content = line.split(['#', '%']).next().unwrap();
+        let fields = content.split_whitespace().collect::<Vec<_>>();
+        match fields.len() {
+            0 => continue,
+            2 | 3 => {}
+            count => {
+                return parse_error(number, format!("expected 2 or 3 fields, found {}", count))
+            }
+        }
+        let mut vertex = |field: &str| -> Result<usize, ReadError> {
+            if options.indexing == Indexing::Labels {
+                let next = ids.len();
+                return Ok(*ids.entry(field.to_string()).or_insert_with(|| {
+                    labels.push(field.to_string());
+                    next
+                }));
+            }
+            let index = match field.parse::<usize>() {
+                Ok(index) => index,
+                Err(_) => return parse_error(number, format!("invalid vertex {:?}", field)),
+            };
+            if options.indexing == Indexing::OneBased {
+                if index == 0 {
+                    return parse_error(number, "vertex 0 in a 1-based edge list");
+                }
+                Ok(index - 1)
+            } else {
+                Ok(index)
+            }
+        };
+        let u = vertex(fields[0])?;
+        let v = vertex(fields[1])?;
+        let weight = match fields.get(2) {
+            Some(field) => {
+                weighted = true;
+                parse_weight(number, field)?
+            }
+            None => 1.,
+        };
+        n = n.max(u + 1).max(v + 1);
+        edges.push((u, v));
+        weights.push(weight);
+    }
+    Ok(Graph {
+        n,
+        edges,
+        weights: weighted.then_some(weights),
+        directed: options.directed,
+        labels: (options.indexing == Indexing::Labels).then_some(labels),
+    })
+}
+
+// Reads a Matrix Market coordinate file as the adjacency matrix of a graph.
+// `pattern` matrices are unweighted, `symmetric` ones undirected. Only the
+// stored triangle of a symmetric matrix is expected.
+pub fn read_matrix_market<R: BufRead>(reader: R) -> Result<Graph, ReadError> {
+    let mut lines = reader.lines().enumerate();
+    let banner = match lines.next() {
+        Some((_, line)) => line?,
+        None => return parse_error(1, "empty file"),
+    };
+    let banner = banner.to_lowercase();
+    let header = banner.split_whitespace().collect::<Vec<_>>();
+    if header.len() != 5 || header[0] != "%%matrixmarket" || header[1] != "matrix" {
+        return parse_error(1, "expected a %%MatrixMarket matrix banner");
+    }
+    if header[2] != "coordinate" {
+        return parse_error(1, format!("unsupported format {:?}", header[2]));
+    }
+    let pattern = match header[3] {
+        "pattern" => true,
+        "real" | "integer" => false,
+        field => return parse_error(1, format!("unsupported field {:?}", field)),
+    };
+    let directed = match header[4] {
+        "general" => true,
+        "symmetric" => false,
+        symmetry => return parse_error(1, format!("unsupported symmetry {:?}", symmetry)),
+    };
+
+    let mut size = None;
+    let mut last = 1;
+    let mut edges = vec![];
+    let mut weights = vec![];
+    for (i, line) in lines {
+        let line = line?;
+        let number = i + 1;
+        last = number;
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.is_empty() || fields[0].starts_with('%') {
+            continue;
+        }
+        let (n, nnz) = match size {
+            Some(size) => size,
+            None => {
+                if fields.len() != 3 {
+                    return parse_error(number, "expected rows, columns and entries");
+                }
+                let values = fields
+                    .iter()
+                    .map(|field| field.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .or_else(|_| parse_error(number, "invalid size line"))?;
+                if values[0] != values[1] {
+                    return parse_error(
+                        number,
+                        format!("matrix is not square ({} x {})", values[0], values[1]),
+                    );
+                }
+                size = Some((values[0], values[2]));
+                continue;
+            }
+        };
+        let expected = if pattern { 2 } else { 3 };
+        if fields.len() != expected {
+            return parse_error(
+                number,
+                format!("expected {} fields, found {}", expected, fields.len()),
+            );
+        }
+        if edges.len() == nnz {
+            return parse_error(number, format!("more than {} entries", nnz));
+        }
+        let vertex = |field: &str| match field.parse::<usize>() {
+            Ok(index) if (1..=n).contains(&index) => Ok(index - 1),
+            _ => parse_error(number, format!("invalid index {:?}", field)),
+        };
+        edges.push((vertex(fields[0])?, vertex(fields[1])?));
+        if !pattern {
+            weights.push(parse_weight(number, fields[2])?);
+        }
+    }
+    let (n, nnz) = match size {
+        Some(size) => size,
+        None => return parse_error(last, "missing size line"),
+    };
+    if edges.len() != nnz {
+        return parse_error(
+            last,
+            format!("expected {} entries, found {}", nnz, edges.len()),
+        );
+    }
+    Ok(Graph {
+        n,
+        edges,
+        weights: (!pattern).then_some(weights),
+        directed,
+        labels: None,
+    })
+}
+
+// Reads a 9th DIMACS challenge shortest path graph (`.gr`): a `p sp n m`
+// problem line followed by `m` weighted arcs `a u v w` with 1-based vertices.
+// The graph is directed.
+pub fn read_dimacs<R: BufRead>(reader: R) -> Result<Graph, ReadError> {
+    let mut size = None;
+    let mut last = 0;
+    let mut edges = vec![];
+    let mut weights = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = i + 1;
+        last = number;
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match (fields.first(), size) {
+            (None | Some(&"c"), _) => {}
+            (Some(&"p"), None) => {
+                if fields.len() != 4 || fields[1] != "sp" {
+                    return parse_error(number, "expected a problem line `p sp n m`");
+                }
+                match (fields[2].parse::<usize>(), fields[3].parse::<usize>()) {
+                    (Ok(n), Ok(m)) => size = Some((n, m)),
+                    _ => return parse_error(number, "invalid problem size"),
+                }
+            }
+            (Some(&"p"), Some(_)) => return parse_error(number, "second problem line"),
+            (Some(&"a"), Some((n, m))) => {
+                if fields.len() != 4 {
+                    return parse_error(number, "expected an arc `a u v w`");
+                }
+                if edges.len() == m {
+                    return parse_error(number, format!("more than {} arcs", m));
+                }
+                let vertex = |field: &str| match field.parse::<usize>() {
+                    Ok(index) if (1..=n).contains(&index) => Ok(index - 1),
+                    _ => parse_error(number, format!("invalid vertex {:?}", field)),
+                };
+                edges.push((vertex(fields[1])?, vertex(fields[2])?));
+                weights.push(parse_weight(number, fields[3])?);
+            }
+            (Some(&"a"), None) => return parse_error(number, "arc before the problem line"),
+            (Some(kind), _) => return parse_error(number, format!("unknown line type {:?}", kind)),
+        }
+    }
+    let (n, m) = match size {
+        Some(size) => size,
+        None => return parse_error(last, "missing problem line"),
+    };
+    if edges.len() != m {
+        return parse_error(last, format!("expected {} arcs, found {}", m, edges.len()));
+    }
+    Ok(Graph {
+        n,
+        edges,
+        weights: Some(weights),
+        directed: true,
+        labels: None,
+    })
+}
+
+// Reads a METIS graph: a header `n m [fmt [ncon]]` and then one line of
+// 1-based neighbors per vertex, each edge listed at both of its endpoints.
+// Vertex sizes and weights are skipped; edge weights are kept if `fmt` has
+// them. The graph is undirected.
+pub fn read_metis<R: BufRead>(reader: R) -> Result<Graph, ReadError> {
+    let mut header = None;
+    let mut vertex = 0;
+    let mut last = 0;
+    let mut edges = vec![];
+    let mut weights = vec![];
+    let mut entries = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = i + 1;
+        last = number;
+        if line.starts_with('%') {
+            continue;
+        }
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let (n, skip, edge_weights) = match header {
+            Some(header) => header,
+            None => {
+                // Blank lines are vertices without neighbors, but not before
+                // the header.
+                if fields.is_empty() {
+                    continue;
+                }
+                if fields.len() < 2 || fields.len() > 4 {
+                    return parse_error(number, "expected a header `n m [fmt [ncon]]`");
+                }
+                let values = fields
+                    .iter()
+                    .map(|field| field.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .or_else(|_| parse_error(number, "invalid header"))?;
+                let format = fields.get(2).copied().unwrap_or("0");
+                if format.len() > 3 || format.chars().any(|c| c != '0' && c != '1') {
+                    return parse_error(number, format!("invalid format {:?}", format));
+                }
+                let format = format!("{:0>3}", format).into_bytes();
+                let ncon = values.get(3).copied().unwrap_or(1);
+                let skip = (format[0] == b'1') as usize + (format[1] == b'1') as usize * ncon;
+                header = Some((values[0], skip, format[2] == b'1'));
+                entries = 2 * values[1];
+                continue;
+            }
+        };
+        if vertex == n {
+            if fields.is_empty() {
+                continue;
+            }
+            return parse_error(number, format!("more than {} vertices", n));
+        }
+        if fields.len() < skip {
+            return parse_error(number, "missing vertex weights");
+        }
+        let stride = if edge_weights { 2 } else { 1 };
+        let neighbors = &fields[skip..];
+        if neighbors.len() % stride != 0 {
+            return parse_error(number, "missing edge weight");
+        }
+        for entry in neighbors.chunks(stride) {
+            let neighbor = match entry[0].parse::<usize>() {
+                Ok(index) if (1..=n).contains(&index) => index - 1,
+                _ => return parse_error(number, format!("invalid vertex {:?}", entry[0])),
+            };
+            if entries == 0 {
+                return parse_error(number, "more edges than in the header");
+            }
+            entries -= 1;
+            // The other endpoint lists the same edge.
+            if vertex < neighbor {
+                edges.push((vertex, neighbor));
+                if edge_weights {
+                    weights.push(parse_weight(number, entry[1])?);
+                }
+            }
+        }
+        vertex += 1;
+    }
+    let (n, _, edge_weights) = match header {
+        Some(header) => header,
+        None => return parse_error(last, "missing header"),
+    };
+    if vertex != n {
+        return parse_error(last, format!("expected {} vertices, found {}", n, vertex));
+    }
+    if entries != 0 {
+        return parse_error(last, "fewer edges than in the header");
+    }
+    Ok(Graph {
+        n,
+        edges,
+        weights: edge_weights.then_some(weights),
+        directed: false,
+        labels: None,
+    })
+}
+
+// Writes a summary of single source results in the style of the 9th DIMACS
+// challenge `.ss` files: the problem, the graph size and weight range, the
+// time per source, and for every source the sum of the distances to the
+// vertices it reaches as a checksum.
+pub fn write_dimacs_ss<W: Write>(
+    writer: &mut W,
+    graph: &Graph,
+    distance: &DistanceMatrix,
+    sources: &[usize],
+    solver: &str,
+    elapsed: Duration,
+) -> io::Result<()> {
+    let n = graph.n;
+    let (min, max) = match &graph.weights {
+        Some(weights) => weights
+            .iter()
+            .fold((f32::INFINITY, 0f32), |(min, max), &w| {
+                (min.min(w), max.max(w))
+            }),
+        None => (1., 1.),
+    };
+    let min = if graph.edges.is_empty() { 0. } else { min };
+    writeln!(writer, "p res sp ss {}", solver)?;
+    writeln!(writer, "g {} {} {} {}", n, graph.edges.len(), min, max)?;
+    writeln!(
+        writer,
+        "t {:.6}",
+        elapsed.as_secs_f64() * 1e3 / sources.len().max(1) as f64
+    )?;
+    for &source in sources {
+        let checksum = distance
+            .row(source)
+            .iter()
+            .filter(|d| d.is_finite())
+            .map(|&d| d as f64)
+            .sum::<f64>();
+        writeln!(writer, "d {} {}", source + 1, checksum)?;
+    }
+    Ok(())
+}
+
+// Element types with a NumPy dtype, stored little-endian.
+pub trait NpyElement: Distance {
+    const DESCR: &'static str;
+}
+
+impl NpyElement for f32 {
+    const DESCR: &'static str = "<f4";
+}
+
+impl NpyElement for u32 {
+    const DESCR: &'static str = "<u4";
+}
+
+impl NpyElement for u16 {
+    const DESCR: &'static str = "<u2";
+}
+
+impl NpyElement for half::f16 {
+    const DESCR: &'static str = "<f2";
+}
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+const NPY_CHUNK_BYTES: usize = 1 << 20;
+
+// Writes a version 1.0 `.npy` file of shape `(n, n)` in C order.
+pub fn write_npy<T: NpyElement, W: Write>(
+    writer: &mut W,
+    matrix: &DistanceMatrix<T>,
+) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        T::DESCR,
+        matrix.n(),
+        matrix.n()
+    );
+    // Magic, version and header length take 10 bytes; the data starts at a
+    // multiple of 64 and the header ends with a newline.
+    let total = (10 + header.len() + 1).next_multiple_of(64);
+    header.push_str(&" ".repeat(total - 10 - header.len() - 1));
+    header.push('\n');
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(bytemuck::cast_slice(matrix.as_slice()))
+}
+
+// Reads a square matrix written by `write_npy` or NumPy. The dtype must be
+// that of `T` and the array in C order. Errors report line 1, the header.
+pub fn read_npy<T: NpyElement, R: Read>(mut reader: R) -> Result<DistanceMatrix<T>, ReadError> {
+    let mut prefix = [0; 8];
+    reader.read_exact(&mut prefix)?;
+    if &prefix[..6] != NPY_MAGIC {
+        return parse_error(1, "not a .npy file");
+    }
+    let header_len = match prefix[6] {
+        1 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        version => return parse_error(1, format!("unsupported version {}", version)),
+    };
+    let mut header = vec![0; header_len];
+    reader.read_exact(&mut header)?;
+    let header = match String::from_utf8(header) {
+        Ok(header) => header,
+        Err(_) => return parse_error(1, "invalid header"),
+    };
+    let value = |key: &str| {
+        let start = header.find(&format!("'{}':", key))? + key.len() + 3;
+        let rest = header[start..].trim_start();
+        let end = if rest.starts_with('(') {
+            rest.find(')')? + 1
+        } else {
+            rest.find([',', '}'])?
+        };
+        Some(rest[..end].trim().to_string())
+    };
+    match value("descr") {
+        Some(descr) if descr.trim_matches(['\'', '"']) == T::DESCR => {}
+        Some(descr) => {
+            return parse_error(1, format!("expected dtype {}, found {}", T::DESCR, descr))
+        }
+        None => return parse_error(1, "missing descr"),
+    }
+    if value("fortran_order").as_deref() != Some("False") {
+        return parse_error(1, "expected an array in C order");
+    }
+    let shape = value("shape")
+        .map(|shape| {
+            shape
+                .trim_matches(['(', ')'])
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::parse::<usize>)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .unwrap_or(Ok(vec![]));
+    let n = match shape.as_deref() {
+        Ok([rows, columns]) if rows == columns => *rows,
+        _ => return parse_error(1, "expected a square matrix"),
+    };
+    let size = std::mem::size_of::<T>();
+    let len = match n
+        .checked_mul(n)
+        .filter(|len| len.checked_mul(size).is_some())
+    {
+        Some(len) => len,
+        None => return parse_error(1, "shape too large"),
+    };
+    // Read in chunks so that a corrupt shape fails on the missing data
+    // instead of allocating the whole matrix up front.
+    let mut data = Vec::new();
+    while data.len() < len {
+        let start = data.len();
+        data.resize(start + (len - start).min(NPY_CHUNK_BYTES / size), T::ZERO);
+        match reader.read_exact(bytemuck::cast_slice_mut(&mut data[start..])) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return parse_error(1, format!("expected {} bytes of data", len * size))
+            }
+            result => result?,
+        }
+    }
+    Ok(DistanceMatrix::new(n, data))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions<'a> {
+    pub separator: char,
+    // Written for unreachable pairs.
+    pub infinity: &'a str,
+    // Header row and first column, one label per vertex.
+    pub labels: Option<&'a [String]>,
+}
+
+impl Default for CsvOptions<'_> {
+    fn default() -> Self {
+        CsvOptions {
+            separator: ',',
+            infinity: "inf",
+            labels: None,
+        }
+    }
+}
+
+pub fn write_csv<T: Distance + fmt::Display, W: Write>(
+    writer: &mut W,
+    matrix: &DistanceMatrix<T>,
+    options: &CsvOptions,
+) -> io::Result<()> {
+    let separator = options.separator.to_string();
+    if let Some(labels) = options.labels {
+        assert_eq!(labels.len(), matrix.n(), "expected one label per vertex");
+        writeln!(writer, "{}{}", separator, labels.join(&separator))?;
+    }
+    for i in 0..matrix.n() {
+        let row = matrix
+            .row(i)
+            .iter()
+            .map(|&d| {
+                if d == T::INFINITY {
+                    options.infinity.to_string()
+                } else {
+                    d.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&separator);
+        match options.labels {
+            Some(labels) => writeln!(writer, "{}{}{}", labels[i], separator, row)?,
+            None => writeln!(writer, "{}", row)?,
+        }
+    }
+    Ok(())
+}
+
+fn parse_weight(line: usize, field: &str) -> Result<f32, ReadError> {
+    match field.parse::<f32>() {
+        Ok(weight) if weight >= 0. => Ok(weight),
+        Ok(_) => parse_error(line, format!("negative weight {}", field)),
+        Err(_) => parse_error(line, format!("invalid weight {:?}", field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error_line(result: Result<Graph, ReadError>) -> (usize, String) {
+        match result {
+            Err(ReadError::Parse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_edge_list() {
+        let input = "# comment\n0 1\n\n1 2 2.5 % trailing\n3 0\n";
+        let graph = read_edge_list(input.as_bytes(), &EdgeListOptions::default()).unwrap();
+        assert_eq!(graph.n, 4);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (3, 0)]);
+        assert_eq!(graph.weights, Some(vec![1., 2.5, 1.]));
+        assert!(!graph.directed);
+        assert_eq!(graph.labels, None);
+
+        let options = EdgeListOptions {
+            indexing: Indexing::OneBased,
+            directed: true,
+        };
+        let graph = read_edge_list("1 2\n2 3\n".as_bytes(), &options).unwrap();
+        assert_eq!(graph.n, 3);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2)]);
+        assert_eq!(graph.weights, None);
+        assert!(graph.directed);
+
+        let options = EdgeListOptions {
+            indexing: Indexing::Labels,
+            directed: false,
+        };
+        let graph =
+            read_edge_list("alice bob\nbob carol\ncarol alice\n".as_bytes(), &options).unwrap();
+        assert_eq!(graph.n, 3);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(
+            graph.labels,
+            Some(vec!["alice".into(), "bob".into(), "carol".into()])
+        );
+    }
+
+    #[test]
+    fn test_read_edge_list_errors() {
+        let options = EdgeListOptions::default();
+        let (line, message) = parse_error_line(read_edge_list("0 1\n1 x\n".as_bytes(), &options));
+        assert_eq!((line, message.as_str()), (2, "invalid vertex \"x\""));
+        let (line, _) = parse_error_line(read_edge_list("0 1\n\n1\n".as_bytes(), &options));
+        assert_eq!(line, 3);
+        let (line, _) = parse_error_line(read_edge_list("0 1 -2\n".as_bytes(), &options));
+        assert_eq!(line, 1);
+        let options = EdgeListOptions {
+            indexing: Indexing::OneBased,
+            directed: false,
+        };
+        let (line, _) = parse_error_line(read_edge_list("1 2\n0 1\n".as_bytes(), &options));
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn test_read_matrix_market() {
+        let input =
+            "%%MatrixMarket matrix coordinate pattern symmetric\n% comment\n3 3 2\n2 1\n3 2\n";
+        let graph = read_matrix_market(input.as_bytes()).unwrap();
+        assert_eq!(graph.n, 3);
+        assert_eq!(graph.edges, vec![(1, 0), (2, 1)]);
+        assert_eq!(graph.weights, None);
+        assert!(!graph.directed);
+
+        let input = "%%MatrixMarket matrix coordinate real general\n2 2 2\n1 2 0.5\n2 1 4\n";
+        let graph = read_matrix_market(input.as_bytes()).unwrap();
+        assert_eq!(graph.edges, vec![(0, 1), (1, 0)]);
+        assert_eq!(graph.weights, Some(vec![0.5, 4.]));
+        assert!(graph.directed);
+    }
+
+    #[test]
+    fn test_read_matrix_market_errors() {
+        for (input, expected) in [
+            ("%%MatrixMarket matrix array real general\n", 1),
+            ("%%MatrixMarket matrix coordinate complex general\n", 1),
+            (
+                "%%MatrixMarket matrix coordinate pattern general\n2 3 0\n",
+                2,
+            ),
+            (
+                "%%MatrixMarket matrix coordinate pattern general\n2 2 1\n1 3\n",
+                3,
+            ),
+            (
+                "%%MatrixMarket matrix coordinate integer general\n2 2 1\n1 2\n",
+                3,
+            ),
+            (
+                "%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 2\n",
+                3,
+            ),
+        ] {
+            let (line, _) = parse_error_line(read_matrix_market(input.as_bytes()));
+            assert_eq!(line, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_read_dimacs() {
+        let input = "c road\np sp 3 3\nc arcs\na 1 2 7\na 2 3 1\na 3 1 2\n";
+        let graph = read_dimacs(input.as_bytes()).unwrap();
+        assert_eq!(graph.n, 3);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(graph.weights, Some(vec![7., 1., 2.]));
+        assert!(graph.directed);
+
+        for (input, expected) in [
+            ("a 1 2 3\n", 1),
+            ("p sp 2 1\na 1 3 1\n", 2),
+            ("p sp 2 2\na 1 2 1\n", 2),
+            ("p sp 2 1\nx\n", 2),
+            ("c empty\n", 1),
+        ] {
+            let (line, _) = parse_error_line(read_dimacs(input.as_bytes()));
+            assert_eq!(line, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_read_metis() {
+        let input =
+            "% triangle with a pendant vertex and an isolated one\n5 4\n2 3\n1 3\n1 2 4\n3\n\n";
+        let graph = read_metis(input.as_bytes()).unwrap();
+        assert_eq!(graph.n, 5);
+        assert_eq!(graph.edges, vec![(0, 1), (0, 2), (1, 2), (2, 3)]);
+        assert_eq!(graph.weights, None);
+        assert!(!graph.directed);
+
+        // Vertex weights with two constraints and edge weights.
+        let input = "3 2 011 2\n1 1 2 5\n1 1 1 5 3 4\n1 1 2 4\n";
+        let graph = read_metis(input.as_bytes()).unwrap();
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2)]);
+        assert_eq!(graph.weights, Some(vec![5., 4.]));
+
+        for (input, expected) in [
+            ("2 1\n2\n", 2),
+            ("2 1\n3\n1\n", 2),
+            ("2 1 1\n2\n1 1\n", 2),
+            ("1 0\n\n2\n", 3),
+        ] {
+            let (line, _) = parse_error_line(read_metis(input.as_bytes()));
+            assert_eq!(line, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_write_dimacs_ss() {
+        let graph = read_dimacs("p sp 3 2\na 1 2 2\na 2 3 3\n".as_bytes()).unwrap();
+        let distance = DistanceMatrix::new(3, crate::cpu::warshall_floyd_graph::<f32>(&graph));
+        let mut output = vec![];
+        write_dimacs_ss(
+            &mut output,
+            &graph,
+            &distance,
+            &[0, 2],
+            "wf",
+            Duration::from_millis(4),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "p res sp ss wf\ng 3 2 2 3\nt 2.000000\nd 1 7\nd 3 0\n"
+        );
+    }
+
+    #[test]
+    fn test_npy() {
+        let matrix = DistanceMatrix::new(3, vec![0., 1., f32::INFINITY, 1., 0., 2., 5., 2., 0.]);
+        let mut output = vec![];
+        write_npy(&mut output, &matrix).unwrap();
+        assert_eq!(&output[..10], b"\x93NUMPY\x01\x00\x76\x00");
+        assert_eq!(output.len(), 128 + 9 * 4);
+        assert!(output[..128].ends_with(b" \n"));
+        assert_eq!(read_npy::<f32, _>(output.as_slice()).unwrap(), matrix);
+
+        let matrix = DistanceMatrix::new(2, vec![0u32, 3, u32::MAX, 0]);
+        let mut output = vec![];
+        write_npy(&mut output, &matrix).unwrap();
+        assert_eq!(read_npy::<u32, _>(output.as_slice()).unwrap(), matrix);
+        let error = read_npy::<f32, _>(output.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: expected dtype <f4, found '<u4'");
+    }
+
+    #[test]
+    fn test_read_npy_numpy_header() {
+        // As written by `numpy.save` for a (2, 2) float32 array.
+        let mut input = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+        let mut header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }".to_string();
+        header += &" ".repeat(117 - header.len());
+        header += "\n";
+        input.extend(header.as_bytes());
+        input.extend(bytemuck::cast_slice(&[0f32, 1., 1., 0.]));
+        let matrix = read_npy::<f32, _>(input.as_slice()).unwrap();
+        assert_eq!(matrix.as_slice(), &[0., 1., 1., 0.]);
+
+        let position = input.windows(5).position(|w| w == b"False").unwrap();
+        input[position..position + 5].copy_from_slice(b"True ");
+        assert!(read_npy::<f32, _>(input.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_read_npy_errors() {
+        let npy = |shape: &str, data: &[f32]| {
+            let mut input = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+            let mut header = format!(
+                "{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}",
+                shape
+            );
+            header += &" ".repeat(117 - header.len());
+            header += "\n";
+            input.extend(header.as_bytes());
+            input.extend(bytemuck::cast_slice(data));
+            read_npy::<f32, _>(input.as_slice())
+        };
+        let message = |result: Result<DistanceMatrix, ReadError>| match result {
+            Err(ReadError::Parse { message, .. }) => message,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+        assert_eq!(
+            message(npy("(2, 2)", &[0., 1., 1.])),
+            "expected 16 bytes of data"
+        );
+        assert_eq!(
+            message(npy("(1000000, 1000000)", &[0.])),
+            "expected 4000000000000 bytes of data"
+        );
+        assert_eq!(
+            message(npy("(18446744073709551615, 18446744073709551615)", &[])),
+            "shape too large"
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let matrix = DistanceMatrix::new(2, vec![0., 1.5, f32::INFINITY, 0.]);
+        let mut output = vec![];
+        write_csv(&mut output, &matrix, &CsvOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "0,1.5\ninf,0\n");
+
+        let labels = vec!["a".to_string(), "b".to_string()];
+        let options = CsvOptions {
+            separator: '\t',
+            infinity: "",
+            labels: Some(&labels),
+        };
+        let mut output = vec![];
+        write_csv(&mut output, &matrix, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\ta\tb\na\t0\t1.5\nb\t\t0\n"
+        );
+    }
+}
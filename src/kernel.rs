@@ -1,29 +1,56 @@
 pub trait Kernel {
-    const WORKGROUP_SIZE_X: usize;
-    const WORKGROUP_SIZE_Y: usize;
-    const WORKGROUP_SIZE_Z: usize;
+    fn workgroup_size(&self) -> [usize; 3];
 
-    fn num_workgroups_x(n: usize) -> usize {
-        (n + Self::WORKGROUP_SIZE_X - 1) / Self::WORKGROUP_SIZE_X
+    fn num_workgroups_x(&self, n: usize) -> usize {
+        n.div_ceil(self.workgroup_size()[0])
     }
 
-    fn num_workgroups_y(n: usize) -> usize {
-        (n + Self::WORKGROUP_SIZE_Y - 1) / Self::WORKGROUP_SIZE_Y
+    fn num_workgroups_y(&self, n: usize) -> usize {
+        n.div_ceil(self.workgroup_size()[1])
     }
 
-    fn num_workgroups_z(n: usize) -> usize {
-        (n + Self::WORKGROUP_SIZE_Z - 1) / Self::WORKGROUP_SIZE_Z
+    fn num_workgroups_z(&self, n: usize) -> usize {
+        n.div_ceil(self.workgroup_size()[2])
     }
 
-    fn num_threads_x(n: usize) -> usize {
-        Self::num_workgroups_x(n) * Self::WORKGROUP_SIZE_X
+    fn num_threads_x(&self, n: usize) -> usize {
+        self.num_workgroups_x(n) * self.workgroup_size()[0]
     }
 
-    fn num_threads_y(n: usize) -> usize {
-        Self::num_workgroups_y(n) * Self::WORKGROUP_SIZE_Y
+    fn num_threads_y(&self, n: usize) -> usize {
+        self.num_workgroups_y(n) * self.workgroup_size()[1]
     }
 
-    fn num_threads_z(n: usize) -> usize {
-        Self::num_workgroups_z(n) * Self::WORKGROUP_SIZE_Z
+    fn num_threads_z(&self, n: usize) -> usize {
+        self.num_workgroups_z(n) * self.workgroup_size()[2]
     }
 }
+
+// Substitutes `{NAME}` placeholders in a WGSL template.
+pub fn render_template(source: &str, values: &[(&str, usize)]) -> String {
+    let mut source = source.to_string();
+    for (name, value) in values {
+        source = source.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    source
+}
+
+pub fn create_bind_group(
+    device: &wgpu::Device,
+    pipeline: &wgpu::ComputePipeline,
+    entries: &[(u32, &wgpu::Buffer)],
+) -> wgpu::BindGroup {
+    let bind_group_layout = pipeline.get_bind_group_layout(0);
+    let entries = entries
+        .iter()
+        .map(|&(binding, buffer)| wgpu::BindGroupEntry {
+            binding,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect::<Vec<_>>();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout: &bind_group_layout,
+        entries: &entries,
+    })
+}
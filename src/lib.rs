@@ -1,4 +1,32 @@
+pub mod apsp;
+pub mod autotune;
+pub mod betweenness;
+pub mod bfs;
 pub mod buffer;
+pub mod centrality;
+pub mod cpu;
+pub mod distance;
+pub mod dynamic;
+#[cfg(feature = "capi")]
+pub mod ffi;
+pub mod generators;
+pub mod graph;
+pub mod io;
 pub mod kernel;
+pub mod matrix;
+pub mod mds;
+pub mod metrics;
+pub mod poller;
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod stress;
 pub mod tropical_matmul;
 pub mod warshall_floyd;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod workspace;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,422 @@
+use crate::{buffer::download_buffer, distance::Distance};
+
+// Dense `n * n` distance matrix in row-major order, without the padding of
+// the device layouts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceMatrix<T = f32> {
+    n: usize,
+    data: Vec<T>,
+}
+
+impl<T: Distance> DistanceMatrix<T> {
+    pub fn new(n: usize, data: Vec<T>) -> DistanceMatrix<T> {
+        assert_eq!(data.len(), n * n, "expected {} elements", n * n);
+        DistanceMatrix { n, data }
+    }
+
+    // Drops the padding of a matrix with `stride` elements per row.
+    pub fn from_padded(data: &[T], n: usize, stride: usize) -> DistanceMatrix<T> {
+        let data = (0..n)
+            .flat_map(|i| data[i * stride..i * stride + n].iter().copied())
+            .collect();
+        DistanceMatrix { n, data }
+    }
+
+    pub async fn download(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        src: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+    ) -> Option<DistanceMatrix<T>> {
+        let data = download_buffer::<T>(device, queue, src).await?;
+        Some(DistanceMatrix::from_padded(&data, n, stride))
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.data[i * self.n + j]
+    }
+
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.data[i * self.n..(i + 1) * self.n]
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    // Device layout with `stride` elements per row and `rows` rows, padded
+    // with infinity.
+    pub fn padded(&self, stride: usize, rows: usize) -> Vec<T> {
+        let mut data = vec![T::INFINITY; stride * rows];
+        for i in 0..self.n {
+            data[i * stride..i * stride + self.n].copy_from_slice(self.row(i));
+        }
+        data
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<T: Distance> DistanceMatrix<T> {
+    // For matrices of graphs converted with `Graph::from_petgraph`.
+    pub fn node_distance<G: petgraph::visit::NodeIndexable>(
+        &self,
+        graph: G,
+        source: G::NodeId,
+        target: G::NodeId,
+    ) -> T {
+        self.get(graph.to_index(source), graph.to_index(target))
+    }
+
+    // Distances from `source` to every node of `graph`.
+    pub fn node_row<'a, G>(
+        &'a self,
+        graph: G,
+        source: G::NodeId,
+    ) -> impl Iterator<Item = (G::NodeId, T)> + 'a
+    where
+        G: petgraph::visit::NodeIndexable + petgraph::visit::IntoNodeIdentifiers + 'a,
+    {
+        let row = self.row(graph.to_index(source));
+        graph
+            .node_identifiers()
+            .map(move |node| (node, row[graph.to_index(node)]))
+    }
+}
+
+// Human readable formats such as JSON get `{"n": .., "rows": [[..], ..]}` with
+// `null` for infinity. Binary formats such as bincode get `n` followed by the
+// raw little-endian rows in chunks of about `CHUNK_BYTES`, so large matrices
+// are neither encoded element by element nor held in a single buffer.
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::DistanceMatrix;
+    use crate::distance::Distance;
+    use serde::{
+        de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Unexpected, Visitor},
+        ser::{SerializeSeq, Serializer},
+        Deserialize, Serialize,
+    };
+    use std::{fmt, marker::PhantomData};
+
+    const CHUNK_BYTES: usize = 1 << 20;
+
+    #[derive(Serialize, Deserialize)]
+    struct Readable<T> {
+        n: usize,
+        rows: Vec<Vec<Option<T>>>,
+    }
+
+    #[derive(Serialize)]
+    struct Binary<'a, T: Distance> {
+        n: u64,
+        chunks: Chunks<'a, T>,
+    }
+
+    struct Chunks<'a, T>(&'a DistanceMatrix<T>);
+
+    impl<'a, T: Distance> Serialize for Chunks<'a, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let bytes = bytemuck::cast_slice::<T, u8>(&self.0.data);
+            let row_bytes = self.0.n * std::mem::size_of::<T>();
+            let rows_per_chunk = (CHUNK_BYTES / row_bytes.max(1)).max(1);
+            let chunks = bytes.chunks((rows_per_chunk * row_bytes).max(1));
+            let mut seq = serializer.serialize_seq(Some(chunks.len()))?;
+            for chunk in chunks {
+                seq.serialize_element(&Bytes(chunk))?;
+            }
+            seq.end()
+        }
+    }
+
+    struct Bytes<'a>(&'a [u8]);
+
+    impl<'a> Serialize for Bytes<'a> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    // Reads `n` and then the chunks straight into the matrix, so neither the
+    // chunks nor an allocation sized by an unchecked `n` are held.
+    struct BinaryVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Distance> Visitor<'de> for BinaryVisitor<T> {
+        type Value = DistanceMatrix<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a distance matrix")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let n = seq
+                .next_element::<u64>()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let len = element_count::<T, A::Error>(n)?;
+            let data = seq
+                .next_element_seed(ChunksSeed::<T>(len, PhantomData))?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Ok(DistanceMatrix {
+                n: n as usize,
+                data,
+            })
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut n = None;
+            let mut data = None;
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "n" => n = Some(map.next_value::<u64>()?),
+                    "chunks" => {
+                        let n = n.ok_or_else(|| de::Error::custom("expected n before chunks"))?;
+                        let len = element_count::<T, A::Error>(n)?;
+                        data = Some(map.next_value_seed(ChunksSeed::<T>(len, PhantomData))?);
+                    }
+                    _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                }
+            }
+            match (n, data) {
+                (Some(n), Some(data)) => Ok(DistanceMatrix {
+                    n: n as usize,
+                    data,
+                }),
+                _ => Err(de::Error::missing_field("chunks")),
+            }
+        }
+    }
+
+    const FIELDS: &[&str] = &["n", "chunks"];
+
+    // Number of elements of an `n * n` matrix, if its bytes are addressable.
+    fn element_count<T, E: de::Error>(n: u64) -> Result<usize, E> {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| n.checked_mul(n))
+            .filter(|len| len.checked_mul(std::mem::size_of::<T>()).is_some())
+            .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(n), &"a smaller matrix"))
+    }
+
+    // The chunks of a matrix with `.0` elements.
+    struct ChunksSeed<T>(usize, PhantomData<T>);
+
+    impl<'de, T: Distance> DeserializeSeed<'de> for ChunksSeed<T> {
+        type Value = Vec<T>;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<T>, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de, T: Distance> Visitor<'de> for ChunksSeed<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "chunks of {} elements in total", self.0)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+            let mut data = Vec::new();
+            while seq
+                .next_element_seed(ChunkSeed {
+                    data: &mut data,
+                    len: self.0,
+                })?
+                .is_some()
+            {}
+            if data.len() != self.0 {
+                return Err(de::Error::invalid_length(data.len(), &self));
+            }
+            Ok(data)
+        }
+    }
+
+    // Appends one chunk of raw bytes to `data`, which must not grow beyond
+    // `len` elements.
+    struct ChunkSeed<'a, T> {
+        data: &'a mut Vec<T>,
+        len: usize,
+    }
+
+    impl<'a, T: Distance> ChunkSeed<'a, T> {
+        fn append<E: de::Error>(self, bytes: &[u8]) -> Result<(), E> {
+            let size = std::mem::size_of::<T>();
+            let elements = bytes.len() / size;
+            if !bytes.len().is_multiple_of(size) || self.data.len() + elements > self.len {
+                return Err(E::invalid_length(
+                    self.data.len() * size + bytes.len(),
+                    &self,
+                ));
+            }
+            // Grows geometrically, but never past the declared size.
+            let needed = self.data.len() + elements;
+            if self.data.capacity() < needed {
+                let target = needed.max(self.data.capacity() * 2).min(self.len);
+                self.data.reserve_exact(target - self.data.len());
+            }
+            self.data.extend(
+                bytes
+                    .chunks_exact(size)
+                    .map(bytemuck::pod_read_unaligned::<T>),
+            );
+            Ok(())
+        }
+    }
+
+    impl<'de, 'a, T: Distance> DeserializeSeed<'de> for ChunkSeed<'a, T> {
+        type Value = ();
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            deserializer.deserialize_bytes(self)
+        }
+    }
+
+    impl<'de, 'a, T: Distance> Visitor<'de> for ChunkSeed<'a, T> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "at most {} bytes of whole elements",
+                self.len * std::mem::size_of::<T>()
+            )
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<(), E> {
+            self.append(v)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            let mut bytes = Vec::new();
+            while let Some(b) = seq.next_element()? {
+                bytes.push(b);
+            }
+            self.append(&bytes)
+        }
+    }
+
+    impl<T: Distance + Serialize> Serialize for DistanceMatrix<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                let rows = (0..self.n)
+                    .map(|i| {
+                        self.row(i)
+                            .iter()
+                            .map(|&d| (d != T::INFINITY).then_some(d))
+                            .collect()
+                    })
+                    .collect();
+                Readable { n: self.n, rows }.serialize(serializer)
+            } else {
+                if cfg!(target_endian = "big") {
+                    return Err(serde::ser::Error::custom(
+                        "binary encoding requires a little-endian target",
+                    ));
+                }
+                Binary {
+                    n: self.n as u64,
+                    chunks: Chunks(self),
+                }
+                .serialize(serializer)
+            }
+        }
+    }
+
+    impl<'de, T: Distance + Deserialize<'de>> Deserialize<'de> for DistanceMatrix<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            if deserializer.is_human_readable() {
+                let Readable { n, rows } = Readable::<T>::deserialize(deserializer)?;
+                if rows.len() != n || rows.iter().any(|row| row.len() != n) {
+                    return Err(de::Error::custom(format!("expected {} rows of {}", n, n)));
+                }
+                let data = rows
+                    .into_iter()
+                    .flatten()
+                    .map(|d| d.unwrap_or(T::INFINITY))
+                    .collect();
+                Ok(DistanceMatrix { n, data })
+            } else {
+                if cfg!(target_endian = "big") {
+                    return Err(de::Error::custom(
+                        "binary encoding requires a little-endian target",
+                    ));
+                }
+                deserializer.deserialize_struct("Binary", FIELDS, BinaryVisitor(PhantomData))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use half::f16;
+
+    #[test]
+    fn test_serde_json() {
+        let matrix = DistanceMatrix::new(2, vec![0., 1.5, f32::INFINITY, 0.]);
+        let json = serde_json::to_string(&matrix).unwrap();
+        assert_eq!(json, r#"{"n":2,"rows":[[0.0,1.5],[null,0.0]]}"#);
+        assert_eq!(
+            serde_json::from_str::<DistanceMatrix>(&json).unwrap(),
+            matrix
+        );
+        assert!(serde_json::from_str::<DistanceMatrix>(r#"{"n":2,"rows":[[0.0]]}"#).is_err());
+    }
+
+    #[test]
+    fn test_serde_bincode() {
+        // 2400 bytes per row, so the rows are split over two chunks.
+        let n = 600;
+        let data = (0..n * n)
+            .map(|i| if i % 7 == 0 { f32::INFINITY } else { i as f32 })
+            .collect();
+        let matrix = DistanceMatrix::new(n, data);
+        let bytes = bincode::serialize(&matrix).unwrap();
+        assert!(bytes.len() < n * n * 4 + 64);
+        assert_eq!(
+            bincode::deserialize::<DistanceMatrix>(&bytes).unwrap(),
+            matrix
+        );
+
+        let matrix = DistanceMatrix::new(3, (0..9).map(|i| f16::from_f32(i as f32)).collect());
+        let bytes = bincode::serialize(&matrix).unwrap();
+        assert_eq!(
+            bincode::deserialize::<DistanceMatrix<f16>>(&bytes).unwrap(),
+            matrix
+        );
+        // `n` no longer matches the number of elements.
+        let mut bytes = bincode::serialize(&DistanceMatrix::new(1, vec![0u32])).unwrap();
+        bytes[0] = 2;
+        assert!(bincode::deserialize::<DistanceMatrix<u32>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_serde_bincode_hostile_size() {
+        // `n`, the number of chunks and one chunk of four bytes.
+        let encode = |n: u64| {
+            let mut bytes = n.to_le_bytes().to_vec();
+            bytes.extend(1u64.to_le_bytes());
+            bytes.extend(4u64.to_le_bytes());
+            bytes.extend(0f32.to_le_bytes());
+            bytes
+        };
+        let error = bincode::deserialize::<DistanceMatrix>(&encode(u64::MAX)).unwrap_err();
+        assert!(error.to_string().contains("a smaller matrix"));
+        // Fails on the missing data instead of allocating 4 TiB up front.
+        let error = bincode::deserialize::<DistanceMatrix>(&encode(1 << 20)).unwrap_err();
+        assert!(error.to_string().contains("invalid length 1"));
+        assert_eq!(
+            bincode::deserialize::<DistanceMatrix>(&encode(1)).unwrap(),
+            DistanceMatrix::new(1, vec![0.])
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{
+    buffer::download_buffer,
+    kernel::{create_bind_group, Kernel},
+};
+
+const MAX_ITERATIONS: usize = 300;
+const TOLERANCE: f32 = 1e-6;
+
+pub struct DoubleCenteringKernel {
+    row_mean_pipeline: wgpu::ComputePipeline,
+    grand_mean_pipeline: wgpu::ComputePipeline,
+    double_centering_pipeline: wgpu::ComputePipeline,
+}
+
+impl DoubleCenteringKernel {
+    pub fn new(device: &wgpu::Device) -> DoubleCenteringKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("double_centering.wgsl"));
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                module: &module,
+                layout: None,
+                entry_point,
+            })
+        };
+        DoubleCenteringKernel {
+            row_mean_pipeline: create_pipeline("row_mean"),
+            grand_mean_pipeline: create_pipeline("grand_mean"),
+            double_centering_pipeline: create_pipeline("double_centering"),
+        }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        distance_buffer: &wgpu::Buffer,
+        mean_buffer: &wgpu::Buffer,
+        centered_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> [wgpu::BindGroup; 3] {
+        [
+            create_bind_group(
+                device,
+                &self.row_mean_pipeline,
+                &[(0, distance_buffer), (1, mean_buffer), (3, params_buffer)],
+            ),
+            create_bind_group(
+                device,
+                &self.grand_mean_pipeline,
+                &[(1, mean_buffer), (3, params_buffer)],
+            ),
+            create_bind_group(
+                device,
+                &self.double_centering_pipeline,
+                &[
+                    (0, distance_buffer),
+                    (1, mean_buffer),
+                    (2, centered_buffer),
+                    (3, params_buffer),
+                ],
+            ),
+        ]
+    }
+
+    pub fn run(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_groups: &[wgpu::BindGroup; 3],
+        n: usize,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.row_mean_pipeline);
+        pass.set_bind_group(0, &bind_groups[0], &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+        pass.set_pipeline(&self.grand_mean_pipeline);
+        pass.set_bind_group(0, &bind_groups[1], &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+        pass.set_pipeline(&self.double_centering_pipeline);
+        pass.set_bind_group(0, &bind_groups[2], &[]);
+        pass.dispatch_workgroups(
+            self.num_workgroups_x(n) as u32,
+            self.num_workgroups_y(n) as u32,
+            1,
+        );
+    }
+}
+
+impl Kernel for DoubleCenteringKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [16, 16, 1]
+    }
+}
+
+pub struct PowerIterationKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl PowerIterationKernel {
+    pub fn new(device: &wgpu::Device) -> PowerIterationKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("power_iteration.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "power_iteration",
+        });
+        PowerIterationKernel { pipeline }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        centered_buffer: &wgpu::Buffer,
+        vector_in_buffer: &wgpu::Buffer,
+        vector_out_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(
+            device,
+            &self.pipeline,
+            &[
+                (0, centered_buffer),
+                (1, vector_in_buffer),
+                (2, vector_out_buffer),
+                (3, params_buffer),
+            ],
+        )
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+    }
+}
+
+impl Kernel for PowerIterationKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [64, 1, 1]
+    }
+}
+
+pub struct ClassicalMds {
+    double_centering: DoubleCenteringKernel,
+    power_iteration: PowerIterationKernel,
+}
+
+impl ClassicalMds {
+    pub fn new(device: &wgpu::Device) -> ClassicalMds {
+        ClassicalMds {
+            double_centering: DoubleCenteringKernel::new(device),
+            power_iteration: PowerIterationKernel::new(device),
+        }
+    }
+
+    // Returns the `n * d` coordinates in row-major order. `distance_buffer`
+    // holds the `n * n` distance matrix with rows `stride` elements apart.
+    // `None` if the graph has no vertices or some distance is not finite, e.g.
+    // for a disconnected graph.
+    pub async fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+        d: usize,
+    ) -> Option<Vec<f32>> {
+        if n == 0 {
+            return None;
+        }
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mean_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: ((n + 1) * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let centered_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (stride * n * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let vector_in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (n * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let vector_out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (n * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let params = vec![n as u32, stride as u32];
+        queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+        let bind_groups = self.double_centering.bind(
+            device,
+            distance_buffer,
+            &mean_buffer,
+            &centered_buffer,
+            &params_buffer,
+        );
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.double_centering.run(&mut encoder, &bind_groups, n);
+        queue.submit(Some(encoder.finish()));
+        // An infinite distance makes its row mean infinite.
+        let mean = download_buffer::<f32>(device, queue, &mean_buffer)
+            .await
+            .unwrap();
+        if !mean.iter().all(|m| m.is_finite()) {
+            return None;
+        }
+
+        let bind_group = self.power_iteration.bind(
+            device,
+            &centered_buffer,
+            &vector_in_buffer,
+            &vector_out_buffer,
+            &params_buffer,
+        );
+        let mut eigenvectors = Vec::<Vec<f32>>::new();
+        let mut coordinates = vec![0.; n * d];
+        let mut largest_eigenvalue = 0f32;
+        for k in 0..d {
+            let mut x = initial_vector(n, k);
+            normalize(&mut x, &eigenvectors);
+            let mut eigenvalue = 0.;
+            for _ in 0..MAX_ITERATIONS {
+                queue.write_buffer(&vector_in_buffer, 0, bytemuck::cast_slice(&x));
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                self.power_iteration.run(&mut encoder, &bind_group, n);
+                queue.submit(Some(encoder.finish()));
+                let mut y = download_buffer::<f32>(device, queue, &vector_out_buffer)
+                    .await
+                    .unwrap();
+                // Once the deflated spectrum is down to rounding noise the
+                // projection stops being orthogonal, so treat it as zero.
+                let norm = normalize(&mut y, &eigenvectors);
+                if norm <= TOLERANCE * largest_eigenvalue {
+                    eigenvalue = 0.;
+                    break;
+                }
+                eigenvalue = norm * x.iter().zip(&y).map(|(a, b)| a * b).sum::<f32>();
+                let delta = x
+                    .iter()
+                    .zip(&y)
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0., f32::max);
+                x = y;
+                if delta < TOLERANCE {
+                    break;
+                }
+            }
+            largest_eigenvalue = largest_eigenvalue.max(eigenvalue.abs());
+            let scale = eigenvalue.max(0.).sqrt();
+            for i in 0..n {
+                coordinates[i * d + k] = x[i] * scale;
+            }
+            eigenvectors.push(x);
+        }
+        Some(coordinates)
+    }
+}
+
+fn initial_vector(n: usize, k: usize) -> Vec<f32> {
+    (0..n)
+        .map(|i| ((i * 31 + k * 17) % 97) as f32 / 97. - 0.5)
+        .collect()
+}
+
+// Removes the components along `basis` and scales `x` to unit length,
+// returning the norm after the projection.
+fn normalize(x: &mut [f32], basis: &[Vec<f32>]) -> f32 {
+    for v in basis {
+        let dot = x.iter().zip(v).map(|(a, b)| a * b).sum::<f32>();
+        for (a, b) in x.iter_mut().zip(v) {
+            *a -= dot * b;
+        }
+    }
+    let norm = x.iter().map(|a| a * a).sum::<f32>().sqrt();
+    if norm > 0. {
+        for a in x.iter_mut() {
+            *a /= norm;
+        }
+    }
+    norm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{init, path_graph, warshall_floyd},
+        warshall_floyd::WarshallFloydKernel,
+    };
+
+    #[tokio::test]
+    async fn test_classical_mds() {
+        let (device, queue) = init().await;
+        let n = 20;
+        let d = 2;
+        let distance_buffer = warshall_floyd(&device, &queue, &path_graph(n), n);
+        let coordinates = ClassicalMds::new(&device)
+            .run(
+                &device,
+                &queue,
+                &distance_buffer,
+                n,
+                WarshallFloydKernel::stride_x(n),
+                d,
+            )
+            .await
+            .unwrap();
+
+        for i in 0..n {
+            for j in 0..n {
+                let dx = coordinates[i * d] - coordinates[j * d];
+                let dy = coordinates[i * d + 1] - coordinates[j * d + 1];
+                let expected = (i as f32 - j as f32).abs();
+                assert!(((dx * dx + dy * dy).sqrt() - expected).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_classical_mds_disconnected() {
+        let (device, queue) = init().await;
+        let n = 6;
+        let distance_buffer = warshall_floyd(&device, &queue, &[(0, 1), (1, 2), (3, 4)], n);
+        let mds = ClassicalMds::new(&device);
+        let coordinates = mds
+            .run(
+                &device,
+                &queue,
+                &distance_buffer,
+                n,
+                WarshallFloydKernel::stride_x(n),
+                2,
+            )
+            .await;
+        assert_eq!(coordinates, None);
+
+        let coordinates = mds.run(&device, &queue, &distance_buffer, 0, 0, 2).await;
+        assert_eq!(coordinates, None);
+    }
+}
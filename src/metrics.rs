@@ -0,0 +1,232 @@
+use crate::{
+    buffer::download_buffer,
+    kernel::{create_bind_group, Kernel},
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RowStats {
+    pub eccentricity: f32,
+    pub sum: f32,
+    pub reachable: u32,
+    pub harmonic: f32,
+}
+
+pub struct RowStatsKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl RowStatsKernel {
+    pub fn new(device: &wgpu::Device) -> RowStatsKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("row_stats.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "row_stats",
+        });
+        RowStatsKernel { pipeline }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        distance_buffer: &wgpu::Buffer,
+        stats_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        create_bind_group(
+            device,
+            &self.pipeline,
+            &[(0, distance_buffer), (1, stats_buffer), (2, params_buffer)],
+        )
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+    }
+}
+
+impl Kernel for RowStatsKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [64, 1, 1]
+    }
+}
+
+pub(crate) async fn row_stats(
+    kernel: &RowStatsKernel,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    distance_buffer: &wgpu::Buffer,
+    n: usize,
+    stride: usize,
+) -> Vec<RowStats> {
+    let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 8,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let stats_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (n * std::mem::size_of::<RowStats>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let params = vec![n as u32, stride as u32];
+    queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+    let bind_group = kernel.bind(device, distance_buffer, &stats_buffer, &params_buffer);
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    kernel.run(&mut encoder, &bind_group, n);
+    queue.submit(Some(encoder.finish()));
+    download_buffer(device, queue, &stats_buffer).await.unwrap()
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphMetrics {
+    pub eccentricity: Vec<f32>,
+    pub diameter: f32,
+    pub radius: f32,
+    pub center: Vec<usize>,
+    pub periphery: Vec<usize>,
+    pub average_shortest_path_length: f64,
+    pub wiener_index: f64,
+}
+
+impl GraphMetrics {
+    // For directed graphs the Wiener index sums over ordered pairs, for
+    // undirected graphs over unordered ones.
+    pub fn from_row_stats(stats: &[RowStats], directed: bool) -> GraphMetrics {
+        let eccentricity = stats.iter().map(|s| s.eccentricity).collect::<Vec<_>>();
+        let diameter = eccentricity.iter().cloned().fold(0., f32::max);
+        let radius = eccentricity.iter().cloned().reduce(f32::min).unwrap_or(0.);
+        let center = (0..stats.len())
+            .filter(|&i| eccentricity[i] == radius)
+            .collect();
+        let periphery = (0..stats.len())
+            .filter(|&i| eccentricity[i] == diameter)
+            .collect();
+        let total = stats.iter().map(|s| s.sum as f64).sum::<f64>();
+        let pairs = stats.iter().map(|s| s.reachable as u64).sum::<u64>();
+        GraphMetrics {
+            eccentricity,
+            diameter,
+            radius,
+            center,
+            periphery,
+            average_shortest_path_length: if pairs > 0 { total / pairs as f64 } else { 0. },
+            wiener_index: if directed { total } else { total / 2. },
+        }
+    }
+}
+
+pub struct Metrics {
+    kernel: RowStatsKernel,
+}
+
+impl Metrics {
+    pub fn new(device: &wgpu::Device) -> Metrics {
+        let kernel = RowStatsKernel::new(device);
+        Metrics { kernel }
+    }
+
+    pub async fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+        directed: bool,
+    ) -> GraphMetrics {
+        let stats = row_stats(&self.kernel, device, queue, distance_buffer, n, stride).await;
+        GraphMetrics::from_row_stats(&stats, directed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{init, path_graph, warshall_floyd},
+        warshall_floyd::WarshallFloydKernel,
+    };
+
+    #[tokio::test]
+    async fn test_metrics_path() {
+        let (device, queue) = init().await;
+        let n = 10;
+        let distance_buffer = warshall_floyd(&device, &queue, &path_graph(n), n);
+        let metrics = Metrics::new(&device)
+            .run(
+                &device,
+                &queue,
+                &distance_buffer,
+                n,
+                WarshallFloydKernel::stride_x(n),
+                false,
+            )
+            .await;
+
+        for i in 0..n {
+            assert_eq!(metrics.eccentricity[i], i.max(n - 1 - i) as f32);
+        }
+        assert_eq!(metrics.diameter, 9.);
+        assert_eq!(metrics.radius, 5.);
+        assert_eq!(metrics.center, vec![4, 5]);
+        assert_eq!(metrics.periphery, vec![0, 9]);
+        assert_eq!(metrics.wiener_index, 165.);
+        assert!((metrics.average_shortest_path_length - 11. / 3.).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_disconnected() {
+        let (device, queue) = init().await;
+        let n = 5;
+        let distance_buffer = warshall_floyd(&device, &queue, &[(0, 1), (1, 2), (3, 4)], n);
+        let metrics = Metrics::new(&device)
+            .run(
+                &device,
+                &queue,
+                &distance_buffer,
+                n,
+                WarshallFloydKernel::stride_x(n),
+                false,
+            )
+            .await;
+
+        assert_eq!(metrics.eccentricity, vec![2., 1., 2., 1., 1.]);
+        assert_eq!(metrics.diameter, 2.);
+        assert_eq!(metrics.radius, 1.);
+        assert_eq!(metrics.center, vec![1, 3, 4]);
+        assert_eq!(metrics.periphery, vec![0, 2]);
+        assert_eq!(metrics.wiener_index, 5.);
+        assert_eq!(metrics.average_shortest_path_length, 1.25);
+    }
+
+    #[test]
+    fn test_metrics_directed() {
+        use crate::{cpu, graph::Graph};
+
+        // 0 -> 1 -> 2: the ordered distances are 1, 2 and 1.
+        let graph = Graph {
+            directed: true,
+            ..Graph::new(3, vec![(0, 1), (1, 2)])
+        };
+        let distance = cpu::warshall_floyd_graph::<f32>(&graph);
+        let metrics = GraphMetrics::from_row_stats(&cpu::row_stats(&distance, 3), true);
+        assert_eq!(metrics.wiener_index, 4.);
+        assert_eq!(metrics.average_shortest_path_length, 4. / 3.);
+        assert_eq!(metrics.eccentricity, vec![2., 1., 0.]);
+
+        let graph = Graph::new(3, vec![(0, 1), (1, 2)]);
+        let distance = cpu::warshall_floyd_graph::<f32>(&graph);
+        let metrics = GraphMetrics::from_row_stats(&cpu::row_stats(&distance, 3), false);
+        assert_eq!(metrics.wiener_index, 4.);
+    }
+}
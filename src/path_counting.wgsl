@@ -0,0 +1,38 @@
+struct Params {
+  size : u32,
+  k : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> buffer_in: array<vec2<f32>>;
+@group(0)
+@binding(1)
+var<storage, write> buffer_out: array<vec2<f32>>;
+@group(0)
+@binding(2)
+var<uniform> params: Params;
+
+// Each element holds the distance and the number of shortest paths.
+@compute
+@workgroup_size(16, 16)
+fn path_counting(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var x : u32 = global_invocation_id.x;
+  var y : u32 = global_invocation_id.y;
+  var k : u32 = params.k;
+  var n : u32 = params.size;
+  if (x < n && y < n) {
+    var a : vec2<f32> = buffer_in[y * n + x];
+    if (x != k && y != k) {
+      var b : vec2<f32> = buffer_in[y * n + k];
+      var c : vec2<f32> = buffer_in[k * n + x];
+      var d : f32 = b.x + c.x;
+      if (d < a.x) {
+        a = vec2<f32>(d, b.y * c.y);
+      } else if (d == a.x && d < 3.4e38) {
+        a.y = a.y + b.y * c.y;
+      }
+    }
+    buffer_out[y * n + x] = a;
+  }
+}
@@ -0,0 +1,218 @@
+use crate::buffer::read_buffer;
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+struct State {
+    pending: usize,
+    // Bumped on every submission and completion; the thread only polls again
+    // once it changes, so it never spins on work that is not submitted yet.
+    events: u64,
+    shutdown: bool,
+}
+
+struct Shared {
+    device: Arc<wgpu::Device>,
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+// Waits on the device from a background thread while any operation started
+// through the poller is outstanding, so its futures can be awaited from any
+// executor without blocking the awaiting thread. Clones share the thread,
+// which stops once the last clone is dropped.
+#[derive(Clone)]
+pub struct Poller {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    shared: Arc<Shared>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+// Keeps the poller busy until dropped. Moved into the wgpu callback that
+// completes the operation.
+struct Pending {
+    shared: Arc<Shared>,
+}
+
+impl Poller {
+    pub fn new(device: Arc<wgpu::Device>) -> Poller {
+        let shared = Arc::new(Shared {
+            device,
+            state: Mutex::new(State {
+                pending: 0,
+                events: 0,
+                shutdown: false,
+            }),
+            condvar: Condvar::new(),
+        });
+        let thread = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("wgpu-poller".into())
+                .spawn(move || poll_loop(&shared))
+                .unwrap()
+        };
+        Poller {
+            inner: Arc::new(Inner {
+                shared,
+                thread: Some(thread),
+            }),
+        }
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.inner.shared.device
+    }
+
+    // Resolves once all work submitted to `queue` so far has completed.
+    pub async fn done(&self, queue: &wgpu::Queue) {
+        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+        let pending = self.pending();
+        queue.on_submitted_work_done(move || {
+            sender.send(()).ok();
+            drop(pending);
+        });
+        self.notify();
+        receiver.receive().await;
+    }
+
+    pub async fn download_buffer<T: bytemuck::Pod + Send>(
+        &self,
+        queue: &wgpu::Queue,
+        src: &wgpu::Buffer,
+    ) -> Option<Vec<T>> {
+        let receiver = read_buffer(self.device(), queue, src, self.pending());
+        self.notify();
+        receiver.receive().await
+    }
+
+    pub async fn download_distance_matrix(
+        &self,
+        queue: &wgpu::Queue,
+        src: &wgpu::Buffer,
+    ) -> Option<Vec<f32>> {
+        self.download_buffer(queue, src).await
+    }
+
+    fn pending(&self) -> Pending {
+        let shared = &self.inner.shared;
+        shared.state.lock().unwrap().pending += 1;
+        Pending {
+            shared: shared.clone(),
+        }
+    }
+
+    // Called once the operation guarded by a `Pending` has been submitted.
+    fn notify(&self) {
+        self.inner.shared.notify(|_| {});
+    }
+}
+
+impl Shared {
+    fn notify(&self, update: impl FnOnce(&mut State)) {
+        let mut state = self.state.lock().unwrap();
+        update(&mut state);
+        state.events += 1;
+        self.condvar.notify_all();
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.shared.notify(|state| state.pending -= 1);
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.shared.notify(|state| state.shutdown = true);
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+fn poll_loop(shared: &Shared) {
+    let mut seen = 0;
+    loop {
+        {
+            let mut state = shared.state.lock().unwrap();
+            while state.pending == 0 && !state.shutdown || state.pending > 0 && state.events == seen
+            {
+                state = shared.condvar.wait(state).unwrap();
+            }
+            if state.pending == 0 {
+                return;
+            }
+            seen = state.events;
+        }
+        // Callbacks run here and release their pending operations.
+        shared.device.poll(wgpu::Maintain::Wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cpu, testing::init, warshall_floyd::WarshallFloyd};
+
+    #[tokio::test]
+    async fn test_poller_concurrent() {
+        let (device, queue) = init().await;
+        let poller = Poller::new(Arc::new(device));
+        let queue = Arc::new(queue);
+        let wf = Arc::new(WarshallFloyd::<u32>::new(poller.device()));
+        let handles = (0..4)
+            .map(|i| {
+                let poller = poller.clone();
+                let queue = queue.clone();
+                let wf = wf.clone();
+                tokio::spawn(async move {
+                    let n = 20 + 10 * i;
+                    let edges = (1..n).map(|j| (j - 1, j)).collect::<Vec<_>>();
+                    let workspace = wf.create_workspace(poller.device(), n);
+                    wf.stage_adjacency_matrix(
+                        poller.device(),
+                        &queue,
+                        &edges,
+                        n,
+                        workspace.in_buffer(),
+                    );
+                    let result = wf.run_async(&poller, &queue, &workspace).await.unwrap();
+                    (edges, n, result)
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let (edges, n, result) = handle.await.unwrap();
+            let expected = cpu::warshall_floyd::<u32>(&edges, n);
+            let stride = result.len() / n;
+            for u in 0..n {
+                assert_eq!(
+                    result[u * stride..u * stride + n],
+                    expected[u * n..(u + 1) * n]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_poller_executor_agnostic() {
+        let (device, queue) = pollster::block_on(init());
+        let poller = Poller::new(Arc::new(device));
+        pollster::block_on(poller.done(&queue));
+        let buffer = poller.device().create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&buffer, 0, bytemuck::cast_slice(&[1u32, 2, 3, 4]));
+        let result = pollster::block_on(poller.download_buffer::<u32>(&queue, &buffer));
+        assert_eq!(result, Some(vec![1, 2, 3, 4]));
+    }
+}
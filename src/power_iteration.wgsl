@@ -0,0 +1,38 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> centered: array<f32>;
+@group(0)
+@binding(1)
+var<storage, read> vector_in: array<f32>;
+@group(0)
+@binding(2)
+var<storage, write> vector_out: array<f32>;
+@group(0)
+@binding(3)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(64)
+fn power_iteration(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var i : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (i >= n) {
+    return;
+  }
+  var s : f32 = 0.;
+  var j : u32 = 0u;
+  loop {
+    if (j >= n) {
+      break;
+    }
+    s = s + centered[i * stride + j] * vector_in[j];
+    j = j + 1u;
+  }
+  vector_out[i] = s;
+}
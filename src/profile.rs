@@ -0,0 +1,349 @@
+use crate::buffer::download_buffer;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+const QUERIES_PER_SET: u32 = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    // Host adjacency matrix to device.
+    Staging,
+    // Per-iteration uniform writes.
+    Upload,
+    // `copy_buffer_to_buffer` between iterations.
+    Copy,
+    Kernel,
+    Download,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Staging => "staging",
+            Phase::Upload => "upload",
+            Phase::Copy => "copy",
+            Phase::Kernel => "kernel",
+            Phase::Download => "download",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimingSource {
+    Timestamp,
+    WallClock,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseProfile {
+    pub phase: Phase,
+    pub total: Duration,
+    pub count: usize,
+}
+
+// Device phases are timed with timestamp queries when `source` is
+// `Timestamp`; host phases recorded through `Profiler::measure` and
+// `Profiler::record` always use the wall clock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub source: TimingSource,
+    pub phases: Vec<PhaseProfile>,
+}
+
+impl Profile {
+    pub fn phase(&self, phase: Phase) -> Option<&PhaseProfile> {
+        self.phases.iter().find(|p| p.phase == phase)
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|p| p.total).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        let phases = self
+            .phases
+            .iter()
+            .map(|p| {
+                format!(
+                    "{{\"phase\":\"{}\",\"seconds\":{},\"count\":{}}}",
+                    p.phase.name(),
+                    p.total.as_secs_f64(),
+                    p.count
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"source\":\"{}\",\"seconds\":{},\"phases\":[{}]}}",
+            match self.source {
+                TimingSource::Timestamp => "timestamp",
+                TimingSource::WallClock => "wall_clock",
+            },
+            self.total().as_secs_f64(),
+            phases.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total().as_secs_f64();
+        writeln!(
+            f,
+            "{:<10} {:>12} {:>8} {:>7}",
+            "phase", "time (ms)", "count", "share"
+        )?;
+        for p in self.phases.iter() {
+            let seconds = p.total.as_secs_f64();
+            writeln!(
+                f,
+                "{:<10} {:>12.3} {:>8} {:>6.1}%",
+                p.phase.name(),
+                seconds * 1e3,
+                p.count,
+                if total > 0. {
+                    seconds / total * 100.
+                } else {
+                    0.
+                }
+            )?;
+        }
+        write!(f, "{:<10} {:>12.3}", "total", total * 1e3)
+    }
+}
+
+// Collects per-phase timings over one or more runs. Each device phase is
+// submitted on its own so that it can be timed in isolation.
+pub struct Profiler {
+    timestamps: bool,
+    query_sets: Vec<wgpu::QuerySet>,
+    // Phase of each pair of timestamp queries, in query order.
+    queries: Vec<Phase>,
+    timings: Vec<(Phase, Duration)>,
+}
+
+impl Profiler {
+    // Uses timestamp queries if the device was created with
+    // `wgpu::Features::TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device) -> Profiler {
+        Profiler {
+            timestamps: device.features().contains(wgpu::Features::TIMESTAMP_QUERY),
+            query_sets: vec![],
+            queries: vec![],
+            timings: vec![],
+        }
+    }
+
+    pub fn source(&self) -> TimingSource {
+        if self.timestamps {
+            TimingSource::Timestamp
+        } else {
+            TimingSource::WallClock
+        }
+    }
+
+    // Records the commands `f` encodes as one occurrence of `phase`.
+    pub fn phase<F: FnOnce(&mut wgpu::CommandEncoder)>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        phase: Phase,
+        f: F,
+    ) {
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if self.timestamps {
+            let index = self.queries.len() as u32 * 2;
+            if index.is_multiple_of(QUERIES_PER_SET) {
+                self.query_sets
+                    .push(device.create_query_set(&wgpu::QuerySetDescriptor {
+                        label: None,
+                        ty: wgpu::QueryType::Timestamp,
+                        count: QUERIES_PER_SET,
+                    }));
+            }
+            let query_set = self.query_sets.last().unwrap();
+            encoder.write_timestamp(query_set, index % QUERIES_PER_SET);
+            f(&mut encoder);
+            encoder.write_timestamp(query_set, index % QUERIES_PER_SET + 1);
+            self.queries.push(phase);
+            queue.submit(Some(encoder.finish()));
+        } else {
+            f(&mut encoder);
+            let start = Instant::now();
+            queue.submit(Some(encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+            self.timings.push((phase, start.elapsed()));
+        }
+    }
+
+    // Times host work such as staging, waiting for the device afterwards.
+    pub fn measure<R, F: FnOnce() -> R>(&mut self, device: &wgpu::Device, phase: Phase, f: F) -> R {
+        let start = Instant::now();
+        let result = f();
+        device.poll(wgpu::Maintain::Wait);
+        self.timings.push((phase, start.elapsed()));
+        result
+    }
+
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        self.timings.push((phase, duration));
+    }
+
+    // Resolves outstanding queries and returns the accumulated profile.
+    pub async fn finish(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Profile {
+        let mut timings = std::mem::take(&mut self.timings);
+        let period = queue.get_timestamp_period() as f64;
+        for (i, query_set) in std::mem::take(&mut self.query_sets).iter().enumerate() {
+            let count = (self.queries.len() * 2 - i * QUERIES_PER_SET as usize)
+                .min(QUERIES_PER_SET as usize) as u32;
+            let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: count as u64 * 8,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            encoder.resolve_query_set(query_set, 0..count, &resolve_buffer, 0);
+            queue.submit(Some(encoder.finish()));
+            let ticks = download_buffer::<u64>(device, queue, &resolve_buffer)
+                .await
+                .unwrap();
+            let offset = i * QUERIES_PER_SET as usize / 2;
+            for (j, pair) in ticks.chunks(2).enumerate() {
+                let nanos = pair[1].saturating_sub(pair[0]) as f64 * period;
+                timings.push((self.queries[offset + j], Duration::from_nanos(nanos as u64)));
+            }
+        }
+        self.queries.clear();
+
+        let mut phases = Vec::<PhaseProfile>::new();
+        for (phase, duration) in timings {
+            match phases.iter_mut().find(|p| p.phase == phase) {
+                Some(p) => {
+                    p.total += duration;
+                    p.count += 1;
+                }
+                None => phases.push(PhaseProfile {
+                    phase,
+                    total: duration,
+                    count: 1,
+                }),
+            }
+        }
+        phases.sort_by_key(|p| p.phase);
+        Profile {
+            source: self.source(),
+            phases,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::RowStatsKernel,
+        testing::{path_graph, warshall_floyd},
+    };
+
+    // Uses timestamp queries where the adapter supports them, otherwise checks
+    // the same counts on the wall clock.
+    #[tokio::test]
+    async fn test_profiler_timestamps() {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .unwrap();
+        let features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+        let source = if features.is_empty() {
+            TimingSource::WallClock
+        } else {
+            TimingSource::Timestamp
+        };
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features,
+                    limits: wgpu::Limits::downlevel_defaults(),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let n = 64;
+        let distance_buffer = warshall_floyd(&device, &queue, &path_graph(n), n);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let stride = crate::warshall_floyd::WarshallFloydKernel::stride_x(n);
+        queue.write_buffer(
+            &params_buffer,
+            0,
+            bytemuck::cast_slice(&[n as u32, stride as u32]),
+        );
+        let stats_size = (n * std::mem::size_of::<crate::metrics::RowStats>()) as u64;
+        let stats_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: stats_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let copy_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: stats_size,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let kernel = RowStatsKernel::new(&device);
+        let bind_group = kernel.bind(&device, &distance_buffer, &stats_buffer, &params_buffer);
+
+        let mut profiler = Profiler::new(&device);
+        assert_eq!(profiler.source(), source);
+        // Two queries per phase, so this spills into a second, partly used
+        // query set.
+        let kernels = QUERIES_PER_SET as usize / 2;
+        let copies = 100;
+        let start = Instant::now();
+        for i in 0..kernels + copies {
+            if i < 2 * copies && i % 2 == 0 {
+                profiler.phase(&device, &queue, Phase::Copy, |encoder| {
+                    encoder.copy_buffer_to_buffer(&stats_buffer, 0, &copy_buffer, 0, stats_size);
+                });
+            } else {
+                profiler.phase(&device, &queue, Phase::Kernel, |encoder| {
+                    kernel.run(encoder, &bind_group, n);
+                });
+            }
+        }
+        let profile = profiler.finish(&device, &queue).await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(profile.source, source);
+        assert_eq!(profile.phases.len(), 2);
+        let copy = profile.phase(Phase::Copy).unwrap();
+        let kernel_profile = profile.phase(Phase::Kernel).unwrap();
+        assert_eq!(copy.count, copies);
+        assert_eq!(kernel_profile.count, kernels);
+        assert!(kernel_profile.total > Duration::ZERO);
+        // Ticks converted with the wrong period would not fit in the wall
+        // clock time of the whole loop.
+        assert!(profile.total() <= elapsed);
+
+        // Finishing starts over with fresh query sets.
+        profiler.phase(&device, &queue, Phase::Kernel, |encoder| {
+            kernel.run(encoder, &bind_group, n);
+        });
+        let profile = profiler.finish(&device, &queue).await;
+        assert_eq!(profile.phases.len(), 1);
+        assert_eq!(profile.phase(Phase::Kernel).unwrap().count, 1);
+    }
+}
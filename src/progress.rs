@@ -0,0 +1,113 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+// Submissions queued ahead of the last reported one. Keeps progress close to
+// what the device has actually finished and bounds the work left to drain
+// after cancelling.
+const MAX_IN_FLIGHT: usize = 8;
+
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// `completed` of `total` passes have finished on the device: pivots for
+// Warshall-Floyd, squarings for tropical matmul.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "run cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub(crate) struct Tracker<'a> {
+    device: &'a wgpu::Device,
+    token: &'a CancellationToken,
+    progress: &'a mut dyn FnMut(Progress),
+    in_flight: VecDeque<wgpu::SubmissionIndex>,
+    completed: usize,
+    total: usize,
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn new(
+        device: &'a wgpu::Device,
+        token: &'a CancellationToken,
+        progress: &'a mut dyn FnMut(Progress),
+        total: usize,
+    ) -> Tracker<'a> {
+        Tracker {
+            device,
+            token,
+            progress,
+            in_flight: VecDeque::new(),
+            completed: 0,
+            total,
+        }
+    }
+
+    // Called before each submission. Once cancelled, waits for the passes
+    // already submitted so the buffers are idle when the run returns.
+    pub(crate) fn check(&mut self) -> Result<(), Cancelled> {
+        if self.token.is_cancelled() {
+            self.device.poll(wgpu::Maintain::Wait);
+            self.in_flight.clear();
+            return Err(Cancelled);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn submitted(&mut self, index: wgpu::SubmissionIndex) {
+        self.in_flight.push_back(index);
+        if self.in_flight.len() > MAX_IN_FLIGHT {
+            self.wait_oldest();
+        }
+    }
+
+    pub(crate) fn finish(mut self) {
+        while !self.in_flight.is_empty() {
+            self.wait_oldest();
+        }
+    }
+
+    fn wait_oldest(&mut self) {
+        let index = self.in_flight.pop_front().unwrap();
+        self.device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        self.completed += 1;
+        (self.progress)(Progress {
+            completed: self.completed,
+            total: self.total,
+        });
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    apsp::{self, Algorithm},
+    centrality::Centrality,
+    graph::Graph,
+    matrix::DistanceMatrix,
+    metrics::Metrics,
+};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArrayMethods};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+use std::sync::OnceLock;
+
+// The adapter is picked with the usual `WGPU_BACKEND` and `WGPU_POWER_PREF`
+// variables, e.g. `WGPU_BACKEND=gl` for llvmpipe. The device is shared by all
+// calls.
+fn context() -> PyResult<&'static (wgpu::Device, wgpu::Queue)> {
+    static CONTEXT: OnceLock<Result<(wgpu::Device, wgpu::Queue), String>> = OnceLock::new();
+    CONTEXT
+        .get_or_init(|| {
+            let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all());
+            let instance = wgpu::Instance::new(backends);
+            let adapter =
+                pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::util::power_preference_from_env().unwrap_or_default(),
+                    ..Default::default()
+                }))
+                .ok_or("no suitable adapter found")?;
+            pollster::block_on(adapter.request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: wgpu::Features::empty(),
+                    limits: adapter.limits(),
+                },
+                None,
+            ))
+            .map_err(|e| e.to_string())
+        })
+        .as_ref()
+        .map_err(|e| PyRuntimeError::new_err(e.clone()))
+}
+
+fn as_array<'py, T: numpy::Element, D: numpy::ndarray::Dimension>(
+    object: &Bound<'py, PyAny>,
+    dtype: &str,
+) -> PyResult<Bound<'py, numpy::PyArray<T, D>>> {
+    let numpy = PyModule::import(object.py(), "numpy")?;
+    let array = numpy.call_method1("ascontiguousarray", (object, dtype))?;
+    Ok(array.cast_into()?)
+}
+
+fn vertex(index: i64, n: usize) -> PyResult<usize> {
+    usize::try_from(index)
+        .ok()
+        .filter(|&i| i < n)
+        .ok_or_else(|| PyValueError::new_err(format!("vertex {} out of range", index)))
+}
+
+// `graph` is either a square scipy sparse matrix, whose entries are the edge
+// weights, or an `(m, 2)` array of edges with optional `weights`. Without `n`
+// the vertices of an edge array are `0..=max`.
+fn read_graph(
+    graph: &Bound<PyAny>,
+    n: Option<usize>,
+    weights: Option<&Bound<PyAny>>,
+    directed: bool,
+) -> PyResult<Graph> {
+    if graph.hasattr("tocoo")? {
+        if weights.is_some() {
+            return Err(PyValueError::new_err(
+                "weights are taken from the entries of a sparse matrix",
+            ));
+        }
+        let coo = graph.call_method0("tocoo")?;
+        let (rows, cols) = coo.getattr("shape")?.extract::<(usize, usize)>()?;
+        if rows != cols || n.is_some_and(|n| n != rows) {
+            return Err(PyValueError::new_err("expected a square matrix"));
+        }
+        let row = as_array::<i64, numpy::Ix1>(&coo.getattr("row")?, "int64")?;
+        let col = as_array::<i64, numpy::Ix1>(&coo.getattr("col")?, "int64")?;
+        let data = as_array::<f32, numpy::Ix1>(&coo.getattr("data")?, "float32")?;
+        let edges = row
+            .readonly()
+            .as_array()
+            .iter()
+            .zip(col.readonly().as_array())
+            .map(|(&u, &v)| Ok((vertex(u, rows)?, vertex(v, rows)?)))
+            .collect::<PyResult<_>>()?;
+        return checked(Graph {
+            n: rows,
+            edges,
+            weights: Some(data.to_vec()?),
+            directed,
+            labels: None,
+        });
+    }
+
+    let edges = as_array::<i64, numpy::Ix2>(graph, "int64")?;
+    let edges = edges.readonly();
+    let edges = edges.as_array();
+    if edges.ncols() != 2 {
+        return Err(PyValueError::new_err("expected an (m, 2) array of edges"));
+    }
+    let n = match n {
+        Some(n) => n,
+        None => edges.iter().map(|&v| v.max(-1) + 1).max().unwrap_or(0) as usize,
+    };
+    let edges = edges
+        .rows()
+        .into_iter()
+        .map(|e| Ok((vertex(e[0], n)?, vertex(e[1], n)?)))
+        .collect::<PyResult<Vec<_>>>()?;
+    let weights = match weights {
+        Some(weights) => {
+            let weights = as_array::<f32, numpy::Ix1>(weights, "float32")?.to_vec()?;
+            if weights.len() != edges.len() {
+                return Err(PyValueError::new_err("expected one weight per edge"));
+            }
+            Some(weights)
+        }
+        None => None,
+    };
+    checked(Graph {
+        n,
+        edges,
+        weights,
+        directed,
+        labels: None,
+    })
+}
+
+fn checked(graph: Graph) -> PyResult<Graph> {
+    graph.validate().map_err(PyValueError::new_err)?;
+    Ok(graph)
+}
+
+// Returns the buffer holding the distance matrix and its stride.
+fn run(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    graph: &Graph,
+    algorithm: Algorithm,
+) -> PyResult<(wgpu::Buffer, usize)> {
+    pollster::block_on(apsp::run(device, queue, graph, algorithm))
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn shortest_paths<'py>(
+    graph: &Bound<'py, PyAny>,
+    n: Option<usize>,
+    weights: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+    algorithm: Algorithm,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let py = graph.py();
+    let graph = read_graph(graph, n, weights, directed)?;
+    let n = graph.n;
+    let distance = if n == 0 {
+        Vec::new()
+    } else {
+        let (device, queue) = context()?;
+        py.detach(|| {
+            let (buffer, stride) = run(device, queue, &graph, algorithm)?;
+            pollster::block_on(DistanceMatrix::<f32>::download(
+                device, queue, &buffer, n, stride,
+            ))
+            .ok_or_else(|| PyRuntimeError::new_err("failed to download the distance matrix"))
+        })?
+        .into_vec()
+    };
+    // Hands the vector over to numpy without copying.
+    distance.into_pyarray(py).reshape([n, n])
+}
+
+// Unreachable pairs are `inf`. Like scipy, graphs are directed by default.
+#[pyfunction]
+#[pyo3(signature = (graph, n=None, weights=None, directed=true))]
+fn floyd_warshall<'py>(
+    graph: &Bound<'py, PyAny>,
+    n: Option<usize>,
+    weights: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    shortest_paths(graph, n, weights, directed, Algorithm::WarshallFloyd)
+}
+
+#[pyfunction]
+#[pyo3(signature = (graph, n=None, weights=None, directed=true))]
+fn tropical_apsp<'py>(
+    graph: &Bound<'py, PyAny>,
+    n: Option<usize>,
+    weights: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    shortest_paths(graph, n, weights, directed, Algorithm::TropicalBlock)
+}
+
+// The distance matrix stays on the device; only the per-vertex values are
+// downloaded.
+#[pyfunction]
+#[pyo3(signature = (graph, n=None, weights=None, directed=true))]
+fn metrics<'py>(
+    graph: &Bound<'py, PyAny>,
+    n: Option<usize>,
+    weights: Option<&Bound<'py, PyAny>>,
+    directed: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let py = graph.py();
+    let graph = read_graph(graph, n, weights, directed)?;
+    let n = graph.n;
+    if n == 0 {
+        return Err(PyValueError::new_err("graph has no vertices"));
+    }
+    let (device, queue) = context()?;
+    let (metrics, closeness, harmonic) = py.detach(|| {
+        let (buffer, stride) = run(device, queue, &graph, Algorithm::WarshallFloyd)?;
+        let centrality = Centrality::new(device);
+        Ok::<_, PyErr>(pollster::block_on(async {
+            (
+                Metrics::new(device)
+                    .run(device, queue, &buffer, n, stride, graph.directed)
+                    .await,
+                centrality
+                    .closeness(device, queue, &buffer, n, stride)
+                    .await,
+                centrality.harmonic(device, queue, &buffer, n, stride).await,
+            )
+        }))
+    })?;
+
+    let result = PyDict::new(py);
+    result.set_item("eccentricity", metrics.eccentricity.into_pyarray(py))?;
+    result.set_item("closeness", closeness.into_pyarray(py))?;
+    result.set_item("harmonic", harmonic.into_pyarray(py))?;
+    result.set_item("diameter", metrics.diameter)?;
+    result.set_item("radius", metrics.radius)?;
+    result.set_item("center", PyArray1::from_vec(py, metrics.center))?;
+    result.set_item("periphery", PyArray1::from_vec(py, metrics.periphery))?;
+    result.set_item(
+        "average_shortest_path_length",
+        metrics.average_shortest_path_length,
+    )?;
+    result.set_item("wiener_index", metrics.wiener_index)?;
+    Ok(result)
+}
+
+#[pymodule]
+fn wgpu_test(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(floyd_warshall, m)?)?;
+    m.add_function(wrap_pyfunction!(tropical_apsp, m)?)?;
+    m.add_function(wrap_pyfunction!(metrics, m)?)?;
+    Ok(())
+}
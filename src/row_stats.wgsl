@@ -0,0 +1,51 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+struct RowStats {
+  eccentricity : f32,
+  sum : f32,
+  reachable : u32,
+  harmonic : f32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> distance: array<f32>;
+@group(0)
+@binding(1)
+var<storage, write> stats: array<RowStats>;
+@group(0)
+@binding(2)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(64)
+fn row_stats(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var i : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (i >= n) {
+    return;
+  }
+  var eccentricity : f32 = 0.;
+  var sum : f32 = 0.;
+  var reachable : u32 = 0u;
+  var harmonic : f32 = 0.;
+  var j : u32 = 0u;
+  loop {
+    if (j >= n) {
+      break;
+    }
+    var d : f32 = distance[i * stride + j];
+    if (j != i && d < 3.4e38) {
+      eccentricity = max(eccentricity, d);
+      sum = sum + d;
+      reachable = reachable + 1u;
+      harmonic = harmonic + 1. / d;
+    }
+    j = j + 1u;
+  }
+  stats[i] = RowStats(eccentricity, sum, reachable, harmonic);
+}
@@ -0,0 +1,318 @@
+use crate::{
+    buffer::download_buffer,
+    kernel::{create_bind_group, Kernel},
+};
+
+const MAX_ITERATIONS: usize = 500;
+const TOLERANCE: f32 = 1e-5;
+
+pub struct StressMajorizationKernel {
+    update_pipeline: wgpu::ComputePipeline,
+    stress_pipeline: wgpu::ComputePipeline,
+}
+
+impl StressMajorizationKernel {
+    pub fn new(device: &wgpu::Device) -> StressMajorizationKernel {
+        let module = device.create_shader_module(wgpu::include_wgsl!("stress_majorization.wgsl"));
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                module: &module,
+                layout: None,
+                entry_point,
+            })
+        };
+        StressMajorizationKernel {
+            update_pipeline: create_pipeline("stress_majorization"),
+            stress_pipeline: create_pipeline("row_stress"),
+        }
+    }
+
+    pub fn bind(
+        &self,
+        device: &wgpu::Device,
+        distance_buffer: &wgpu::Buffer,
+        position_in_buffer: &wgpu::Buffer,
+        position_out_buffer: &wgpu::Buffer,
+        stress_buffer: &wgpu::Buffer,
+        params_buffer: &wgpu::Buffer,
+    ) -> [wgpu::BindGroup; 2] {
+        [
+            create_bind_group(
+                device,
+                &self.update_pipeline,
+                &[
+                    (0, distance_buffer),
+                    (1, position_in_buffer),
+                    (2, position_out_buffer),
+                    (4, params_buffer),
+                ],
+            ),
+            create_bind_group(
+                device,
+                &self.stress_pipeline,
+                &[
+                    (0, distance_buffer),
+                    (2, position_out_buffer),
+                    (3, stress_buffer),
+                    (4, params_buffer),
+                ],
+            ),
+        ]
+    }
+
+    pub fn run(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_groups: &[wgpu::BindGroup; 2],
+        n: usize,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        pass.set_pipeline(&self.update_pipeline);
+        pass.set_bind_group(0, &bind_groups[0], &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+        pass.set_pipeline(&self.stress_pipeline);
+        pass.set_bind_group(0, &bind_groups[1], &[]);
+        pass.dispatch_workgroups(self.num_workgroups_x(n) as u32, 1, 1);
+    }
+}
+
+impl Kernel for StressMajorizationKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [64, 1, 1]
+    }
+}
+
+pub struct StressMajorization {
+    kernel: StressMajorizationKernel,
+}
+
+impl StressMajorization {
+    pub fn new(device: &wgpu::Device) -> StressMajorization {
+        let kernel = StressMajorizationKernel::new(device);
+        StressMajorization { kernel }
+    }
+
+    // `initial` holds 2 or 3 coordinates per vertex; the returned layout has
+    // the same dimension, followed by its stress. `None` if the graph has no
+    // vertices or `initial` has another shape.
+    pub async fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        distance_buffer: &wgpu::Buffer,
+        n: usize,
+        stride: usize,
+        initial: &[f32],
+    ) -> Option<(Vec<f32>, f32)> {
+        let dim = dimension(initial, n)?;
+        let size = n * 16;
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let position_in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let position_out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let stress_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (n * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let params = vec![n as u32, stride as u32];
+        queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+        queue.write_buffer(
+            &position_in_buffer,
+            0,
+            bytemuck::cast_slice(&to_vec4(initial, dim)),
+        );
+        let bind_groups = self.kernel.bind(
+            device,
+            distance_buffer,
+            &position_in_buffer,
+            &position_out_buffer,
+            &stress_buffer,
+            &params_buffer,
+        );
+
+        let mut stress = f32::INFINITY;
+        for _ in 0..MAX_ITERATIONS {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            self.kernel.run(&mut encoder, &bind_groups, n);
+            encoder.copy_buffer_to_buffer(
+                &position_out_buffer,
+                0,
+                &position_in_buffer,
+                0,
+                size as u64,
+            );
+            queue.submit(Some(encoder.finish()));
+            let row_stress = download_buffer::<f32>(device, queue, &stress_buffer)
+                .await
+                .unwrap();
+            let s = row_stress.iter().sum::<f32>();
+            let converged = stress - s <= TOLERANCE * stress;
+            stress = s;
+            if converged {
+                break;
+            }
+        }
+        let position = download_buffer::<[f32; 4]>(device, queue, &position_out_buffer)
+            .await
+            .unwrap();
+        Some((from_vec4(&position, dim), stress))
+    }
+}
+
+pub fn stress_majorization_cpu(
+    distance: &[f32],
+    n: usize,
+    stride: usize,
+    initial: &[f32],
+) -> Option<(Vec<f32>, f32)> {
+    let dim = dimension(initial, n)?;
+    let mut position = to_vec4(initial, dim);
+    let mut stress = f32::INFINITY;
+    for _ in 0..MAX_ITERATIONS {
+        let mut next = position.clone();
+        for i in 0..n {
+            let mut numerator = [0.; 3];
+            let mut denominator = 0.;
+            for j in 0..n {
+                let d = distance[i * stride + j];
+                if j == i || d <= 0. || d == f32::INFINITY {
+                    continue;
+                }
+                let w = 1. / (d * d);
+                let l = norm(&position[i], &position[j]);
+                for k in 0..3 {
+                    numerator[k] += w * position[j][k];
+                    if l > 0. {
+                        numerator[k] += w * d * (position[i][k] - position[j][k]) / l;
+                    }
+                }
+                denominator += w;
+            }
+            if denominator > 0. {
+                for k in 0..3 {
+                    next[i][k] = numerator[k] / denominator;
+                }
+            }
+        }
+        position = next;
+        let s = stress_cpu(distance, n, stride, &position);
+        let converged = stress - s <= TOLERANCE * stress;
+        stress = s;
+        if converged {
+            break;
+        }
+    }
+    Some((from_vec4(&position, dim), stress))
+}
+
+fn dimension(initial: &[f32], n: usize) -> Option<usize> {
+    match initial.len().checked_div(n)? {
+        dim @ (2 | 3) if initial.len() == n * dim => Some(dim),
+        _ => None,
+    }
+}
+
+fn stress_cpu(distance: &[f32], n: usize, stride: usize, position: &[[f32; 4]]) -> f32 {
+    let mut s = 0.;
+    for i in 0..n {
+        for j in i + 1..n {
+            let d = distance[i * stride + j];
+            if d > 0. && d < f32::INFINITY {
+                let e = norm(&position[i], &position[j]) - d;
+                s += e * e / (d * d);
+            }
+        }
+    }
+    s
+}
+
+fn norm(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    (0..3).map(|k| (a[k] - b[k]).powi(2)).sum::<f32>().sqrt()
+}
+
+fn to_vec4(position: &[f32], dim: usize) -> Vec<[f32; 4]> {
+    position
+        .chunks(dim)
+        .map(|p| {
+            let mut q = [0.; 4];
+            q[..dim].copy_from_slice(p);
+            q
+        })
+        .collect()
+}
+
+fn from_vec4(position: &[[f32; 4]], dim: usize) -> Vec<f32> {
+    position.iter().flat_map(|p| p[..dim].to_vec()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::download_distance_matrix,
+        testing::{init, warshall_floyd},
+        warshall_floyd::WarshallFloydKernel,
+    };
+
+    #[tokio::test]
+    async fn test_stress_majorization() {
+        let (device, queue) = init().await;
+        let n = 12;
+        let stride = WarshallFloydKernel::stride_x(n);
+        let edges = (0..n).map(|i| (i, (i + 1) % n)).collect::<Vec<_>>();
+        let distance_buffer = warshall_floyd(&device, &queue, &edges, n);
+        let distance = download_distance_matrix(&device, &queue, &distance_buffer)
+            .await
+            .unwrap();
+        let initial = (0..2 * n)
+            .map(|i| ((i * 37) % 23) as f32 / 23.)
+            .collect::<Vec<_>>();
+
+        let stress_majorization = StressMajorization::new(&device);
+        let (position, stress) = stress_majorization
+            .run(&device, &queue, &distance_buffer, n, stride, &initial)
+            .await
+            .unwrap();
+        let (expected_position, expected_stress) =
+            stress_majorization_cpu(&distance, n, stride, &initial).unwrap();
+
+        let initial_stress = stress_cpu(&distance, n, stride, &to_vec4(&initial, 2));
+        assert!(stress < initial_stress);
+        assert!((stress - expected_stress).abs() < 1e-3);
+        for (a, b) in position.iter().zip(&expected_position) {
+            assert!((a - b).abs() < 1e-2);
+        }
+        assert_eq!(
+            stress_majorization
+                .run(&device, &queue, &distance_buffer, n, stride, &initial[1..])
+                .await,
+            None
+        );
+    }
+
+    #[test]
+    fn test_stress_majorization_invalid() {
+        assert_eq!(stress_majorization_cpu(&[0.; 9], 3, 3, &[0.; 7]), None);
+        assert_eq!(stress_majorization_cpu(&[0.; 9], 3, 3, &[0.; 12]), None);
+        assert_eq!(stress_majorization_cpu(&[], 0, 0, &[]), None);
+    }
+}
@@ -0,0 +1,83 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> distance: array<f32>;
+@group(0)
+@binding(1)
+var<storage, read> position_in: array<vec4<f32>>;
+@group(0)
+@binding(2)
+var<storage, read_write> position_out: array<vec4<f32>>;
+@group(0)
+@binding(3)
+var<storage, write> stress: array<f32>;
+@group(0)
+@binding(4)
+var<uniform> params: Params;
+
+@compute
+@workgroup_size(64)
+fn stress_majorization(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var i : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (i >= n) {
+    return;
+  }
+  var xi : vec3<f32> = position_in[i].xyz;
+  var numerator : vec3<f32> = vec3<f32>(0., 0., 0.);
+  var denominator : f32 = 0.;
+  var j : u32 = 0u;
+  loop {
+    if (j >= n) {
+      break;
+    }
+    var d : f32 = distance[i * stride + j];
+    if (j != i && d > 0. && d < 3.4e38) {
+      var w : f32 = 1. / (d * d);
+      var xj : vec3<f32> = position_in[j].xyz;
+      var l : f32 = length(xi - xj);
+      numerator = numerator + w * xj;
+      if (l > 0.) {
+        numerator = numerator + w * d * (xi - xj) / l;
+      }
+      denominator = denominator + w;
+    }
+    j = j + 1u;
+  }
+  if (denominator > 0.) {
+    position_out[i] = vec4<f32>(numerator / denominator, 0.);
+  } else {
+    position_out[i] = vec4<f32>(xi, 0.);
+  }
+}
+
+@compute
+@workgroup_size(64)
+fn row_stress(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
+  var i : u32 = global_invocation_id.x;
+  var n : u32 = params.n;
+  var stride : u32 = params.stride;
+  if (i >= n) {
+    return;
+  }
+  var xi : vec3<f32> = position_out[i].xyz;
+  var s : f32 = 0.;
+  var j : u32 = i + 1u;
+  loop {
+    if (j >= n) {
+      break;
+    }
+    var d : f32 = distance[i * stride + j];
+    if (d > 0. && d < 3.4e38) {
+      var e : f32 = length(xi - position_out[j].xyz) - d;
+      s = s + e * e / (d * d);
+    }
+    j = j + 1u;
+  }
+  stress[i] = s;
+}
@@ -0,0 +1,48 @@
+pub async fn init() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .unwrap();
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+}
+
+pub fn path_graph(n: usize) -> Vec<(usize, usize)> {
+    (1..n).map(|i| (i - 1, i)).collect()
+}
+
+pub fn warshall_floyd(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    edges: &[(usize, usize)],
+    n: usize,
+) -> wgpu::Buffer {
+    use crate::warshall_floyd::{stage_adjacency_matrix, WarshallFloyd};
+
+    let size = WarshallFloyd::<f32>::buffer_size(n);
+    let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: size as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: size as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    stage_adjacency_matrix(device, queue, edges, n, &in_buffer);
+    WarshallFloyd::<f32>::new(device).run(device, queue, &in_buffer, &out_buffer, n);
+    out_buffer
+}
@@ -1,6 +1,19 @@
-use crate::{buffer::stage_adjacency_matrix, kernel::Kernel};
+use crate::{
+    buffer::{stage_adjacency_matrix, stage_distance_matrix, stage_graph},
+    distance::{create_shader_module, Distance},
+    graph::Graph,
+    kernel::{render_template, Kernel},
+    matrix::DistanceMatrix,
+    poller::Poller,
+    profile::{Phase, Profiler},
+    progress::{CancellationToken, Cancelled, Progress, Tracker},
+    workspace::Workspace,
+};
+use std::marker::PhantomData;
 
 pub trait TropicalMatmulKernel: Kernel {
+    type Element: Distance;
+
     fn pipeline(&self) -> &wgpu::ComputePipeline;
 
     fn bind(
@@ -33,73 +46,198 @@ pub trait TropicalMatmulKernel: Kernel {
 
     fn run(&self, encoder: &mut wgpu::CommandEncoder, bind_group: &wgpu::BindGroup, n: usize) {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
-        pass.set_pipeline(&self.pipeline());
+        pass.set_pipeline(self.pipeline());
         pass.set_bind_group(0, bind_group, &[]);
+        let stride = self.stride(n);
         pass.dispatch_workgroups(
-            Self::num_workgroups_x(n) as u32,
-            Self::num_workgroups_y(n) as u32,
+            self.num_workgroups_x(stride / Self::Element::LANES) as u32,
+            self.num_workgroups_y(stride) as u32,
             1,
         );
     }
 
-    fn buffer_size(n: usize) -> usize {
-        Self::num_threads_x(n) * Self::num_threads_y(n) * 4
+    // Rows are padded so that every thread owns whole words.
+    fn stride(&self, n: usize) -> usize {
+        self.num_threads_x(n)
+            .next_multiple_of(self.num_threads_x(1) * Self::Element::LANES)
+    }
+
+    fn buffer_size(&self, n: usize) -> usize {
+        self.stride(n) * self.stride(n) * std::mem::size_of::<Self::Element>()
     }
 }
 
-pub struct NaiveTropicalMatmulKernel {
+pub struct NaiveTropicalMatmulKernel<T: Distance = f32> {
     pipeline: wgpu::ComputePipeline,
+    workgroup_size: [usize; 2],
+    element: PhantomData<T>,
 }
 
-impl NaiveTropicalMatmulKernel {
-    pub fn new(device: &wgpu::Device) -> NaiveTropicalMatmulKernel {
-        let module = device.create_shader_module(wgpu::include_wgsl!("tropical_matmul.wgsl"));
+impl<T: Distance> NaiveTropicalMatmulKernel<T> {
+    pub fn new(device: &wgpu::Device) -> NaiveTropicalMatmulKernel<T> {
+        Self::with_workgroup_size(device, 1, 1)
+    }
+
+    pub fn with_workgroup_size(
+        device: &wgpu::Device,
+        x: usize,
+        y: usize,
+    ) -> NaiveTropicalMatmulKernel<T> {
+        let source = render_template(include_str!("tropical_matmul.wgsl"), &[("X", x), ("Y", y)]);
+        let module = create_shader_module::<T>(device, &source);
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: None,
             module: &module,
             layout: None,
             entry_point: "tropical_matmul",
         });
-        NaiveTropicalMatmulKernel { pipeline }
+        NaiveTropicalMatmulKernel {
+            pipeline,
+            workgroup_size: [x, y],
+            element: PhantomData,
+        }
     }
 }
 
-impl Kernel for NaiveTropicalMatmulKernel {
-    const WORKGROUP_SIZE_X: usize = 1;
-    const WORKGROUP_SIZE_Y: usize = 1;
-    const WORKGROUP_SIZE_Z: usize = 1;
+impl<T: Distance> Kernel for NaiveTropicalMatmulKernel<T> {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [self.workgroup_size[0], self.workgroup_size[1], 1]
+    }
 }
 
-impl TropicalMatmulKernel for NaiveTropicalMatmulKernel {
+impl<T: Distance> TropicalMatmulKernel for NaiveTropicalMatmulKernel<T> {
+    type Element = T;
+
     fn pipeline(&self) -> &wgpu::ComputePipeline {
         &self.pipeline
     }
 }
 
-pub struct BlockedTropicalMatmulKernel {
+pub struct BlockedTropicalMatmulKernel<T: Distance = f32> {
     pipeline: wgpu::ComputePipeline,
+    tile_size: usize,
+    element: PhantomData<T>,
 }
 
-impl BlockedTropicalMatmulKernel {
-    pub fn new(device: &wgpu::Device) -> BlockedTropicalMatmulKernel {
-        let module = device.create_shader_module(wgpu::include_wgsl!("tropical_matmul_block.wgsl"));
+impl<T: Distance> BlockedTropicalMatmulKernel<T> {
+    pub fn new(device: &wgpu::Device) -> BlockedTropicalMatmulKernel<T> {
+        Self::with_tile_size(device, 16)
+    }
+
+    // `tile_size * tile_size` threads per workgroup share one tile of each
+    // operand in workgroup memory.
+    pub fn with_tile_size(
+        device: &wgpu::Device,
+        tile_size: usize,
+    ) -> BlockedTropicalMatmulKernel<T> {
+        let source = render_template(
+            include_str!("tropical_matmul_block.wgsl"),
+            &[("TILE", tile_size), ("TILE2", tile_size * tile_size)],
+        );
+        let module = create_shader_module::<T>(device, &source);
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: None,
             module: &module,
             layout: None,
             entry_point: "tropical_matmul",
         });
-        BlockedTropicalMatmulKernel { pipeline }
+        BlockedTropicalMatmulKernel {
+            pipeline,
+            tile_size,
+            element: PhantomData,
+        }
     }
 }
 
-impl Kernel for BlockedTropicalMatmulKernel {
-    const WORKGROUP_SIZE_X: usize = 16;
-    const WORKGROUP_SIZE_Y: usize = 16;
-    const WORKGROUP_SIZE_Z: usize = 1;
+impl<T: Distance> Kernel for BlockedTropicalMatmulKernel<T> {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [self.tile_size, self.tile_size, 1]
+    }
+}
+
+impl<T: Distance> TropicalMatmulKernel for BlockedTropicalMatmulKernel<T> {
+    type Element = T;
+
+    fn pipeline(&self) -> &wgpu::ComputePipeline {
+        &self.pipeline
+    }
+}
+
+// f32 only. Each thread keeps an `m`x`m` block of outputs in registers, so a
+// workgroup of 16x16 threads covers a `16 * m` square tile. `m` must be a
+// multiple of 4 for the vec4 loads and stores.
+pub struct RegisterTiledTropicalMatmulKernel {
+    pipeline: wgpu::ComputePipeline,
+    micro_tile_size: usize,
+}
+
+impl RegisterTiledTropicalMatmulKernel {
+    const WORKGROUP_SIZE: usize = 16;
+
+    pub fn new(device: &wgpu::Device) -> RegisterTiledTropicalMatmulKernel {
+        Self::with_micro_tile_size(device, 4)
+    }
+
+    pub fn with_micro_tile_size(
+        device: &wgpu::Device,
+        m: usize,
+    ) -> RegisterTiledTropicalMatmulKernel {
+        assert!(m > 0 && m.is_multiple_of(4));
+        let source = render_template(
+            include_str!("tropical_matmul_register.wgsl"),
+            &[
+                ("TILE_K4", 64 * m),
+                ("TILE_K", 256 * m),
+                ("TILE4", 4 * m),
+                ("TILE", 16 * m),
+                ("ACC", m * m / 4),
+                ("LOADS", m / 4),
+                ("M4", m / 4),
+                ("M", m),
+            ],
+        );
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            module: &module,
+            layout: None,
+            entry_point: "tropical_matmul",
+        });
+        RegisterTiledTropicalMatmulKernel {
+            pipeline,
+            micro_tile_size: m,
+        }
+    }
+}
+
+impl Kernel for RegisterTiledTropicalMatmulKernel {
+    fn workgroup_size(&self) -> [usize; 3] {
+        [Self::WORKGROUP_SIZE, Self::WORKGROUP_SIZE, 1]
+    }
+
+    fn num_workgroups_x(&self, n: usize) -> usize {
+        n.div_ceil(Self::WORKGROUP_SIZE * self.micro_tile_size)
+    }
+
+    fn num_workgroups_y(&self, n: usize) -> usize {
+        n.div_ceil(Self::WORKGROUP_SIZE * self.micro_tile_size)
+    }
+
+    fn num_threads_x(&self, n: usize) -> usize {
+        self.num_workgroups_x(n) * Self::WORKGROUP_SIZE * self.micro_tile_size
+    }
+
+    fn num_threads_y(&self, n: usize) -> usize {
+        self.num_workgroups_y(n) * Self::WORKGROUP_SIZE * self.micro_tile_size
+    }
 }
 
-impl TropicalMatmulKernel for BlockedTropicalMatmulKernel {
+impl TropicalMatmulKernel for RegisterTiledTropicalMatmulKernel {
+    type Element = f32;
+
     fn pipeline(&self) -> &wgpu::ComputePipeline {
         &self.pipeline
     }
@@ -114,6 +252,20 @@ impl<K: TropicalMatmulKernel> TropicalMatmul<K> {
         TropicalMatmul { kernel }
     }
 
+    pub fn stride(&self, n: usize) -> usize {
+        self.kernel.stride(n)
+    }
+
+    // Largest n whose padded matrix fits in a single storage binding.
+    pub fn max_n(&self, limits: &wgpu::Limits) -> usize {
+        let max_size = limits.max_storage_buffer_binding_size as usize;
+        let mut n = ((max_size / std::mem::size_of::<K::Element>()) as f64).sqrt() as usize;
+        while n > 0 && self.kernel.buffer_size(n) > max_size {
+            n -= 1;
+        }
+        n
+    }
+
     pub fn run(
         &self,
         device: &wgpu::Device,
@@ -122,7 +274,41 @@ impl<K: TropicalMatmulKernel> TropicalMatmul<K> {
         out_buffer: &wgpu::Buffer,
         n: usize,
     ) {
-        let size = K::buffer_size(n);
+        self.run_passes(device, queue, in_buffer, out_buffer, n, None)
+            .unwrap();
+    }
+
+    // Same as `run`, but reports every finished squaring to `progress` and
+    // stops before the next submission once `token` is cancelled. Waits for
+    // the device before returning.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_cancellable<F: FnMut(Progress)>(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        token: &CancellationToken,
+        mut progress: F,
+    ) -> Result<(), Cancelled> {
+        let total = n.next_power_of_two().trailing_zeros() as usize;
+        let mut tracker = Tracker::new(device, token, &mut progress, total);
+        self.run_passes(device, queue, in_buffer, out_buffer, n, Some(&mut tracker))?;
+        tracker.finish();
+        Ok(())
+    }
+
+    fn run_passes(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        mut tracker: Option<&mut Tracker>,
+    ) -> Result<(), Cancelled> {
+        let size = self.kernel.buffer_size(n);
         let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
             size: 8,
@@ -131,32 +317,122 @@ impl<K: TropicalMatmulKernel> TropicalMatmul<K> {
         });
         let bind_group = self
             .kernel
-            .bind(&device, &in_buffer, &out_buffer, &params_buffer);
+            .bind(device, in_buffer, out_buffer, &params_buffer);
 
         let mut params_buffer_staging = wgpu::util::StagingBelt::new(8);
         let mut k = 1;
         while k < n {
+            if let Some(tracker) = tracker.as_mut() {
+                tracker.check()?;
+            }
             let mut encoder =
                 device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-            let params = vec![n as u32, K::num_threads_x(n) as u32];
+            let params = vec![n as u32, self.stride(n) as u32];
             params_buffer_staging
                 .write_buffer(
                     &mut encoder,
                     &params_buffer,
                     0,
                     std::num::NonZeroU64::new(8).unwrap(),
-                    &device,
+                    device,
                 )
                 .copy_from_slice(bytemuck::cast_slice(&params));
             params_buffer_staging.finish();
             if k != 1 {
-                encoder.copy_buffer_to_buffer(&out_buffer, 0, &in_buffer, 0, size as u64);
+                encoder.copy_buffer_to_buffer(out_buffer, 0, in_buffer, 0, size as u64);
             }
             self.kernel.run(&mut encoder, &bind_group, n);
-            queue.submit(Some(encoder.finish()));
+            let index = queue.submit(Some(encoder.finish()));
+            if let Some(tracker) = tracker.as_mut() {
+                tracker.submitted(index);
+            }
             params_buffer_staging.recall();
             k *= 2;
         }
+        Ok(())
+    }
+
+    pub fn create_workspace(&self, device: &wgpu::Device, n: usize) -> Workspace {
+        Workspace::new(
+            device,
+            n,
+            self.kernel.buffer_size(n),
+            &[[n as u32, self.stride(n) as u32]],
+            |in_buffer, out_buffer, params_buffer| {
+                self.kernel
+                    .bind(device, in_buffer, out_buffer, params_buffer)
+            },
+        )
+    }
+
+    // Runs on the matrix staged in `workspace.in_buffer()` with a single
+    // submission. The input buffer is overwritten.
+    pub fn run_workspace(&self, device: &wgpu::Device, queue: &wgpu::Queue, workspace: &Workspace) {
+        let n = workspace.n();
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        workspace.encode_params(&mut encoder, 0);
+        let mut k = 1;
+        while k < n {
+            if k != 1 {
+                workspace.encode_feedback(&mut encoder);
+            }
+            self.kernel.run(&mut encoder, workspace.bind_group(), n);
+            k *= 2;
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+
+    // Runs on `workspace` and resolves with the distance matrix once the
+    // device is done, without blocking the awaiting thread.
+    pub async fn run_async(
+        &self,
+        poller: &Poller,
+        queue: &wgpu::Queue,
+        workspace: &Workspace,
+    ) -> Option<Vec<K::Element>> {
+        self.run_workspace(poller.device(), queue, workspace);
+        poller.download_buffer(queue, workspace.out_buffer()).await
+    }
+
+    // Same as `run`, but submits every phase separately and records it in
+    // `profiler`.
+    pub fn run_profiled(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        profiler: &mut Profiler,
+    ) {
+        let size = self.kernel.buffer_size(n);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self
+            .kernel
+            .bind(device, in_buffer, out_buffer, &params_buffer);
+        let params = vec![n as u32, self.stride(n) as u32];
+        profiler.phase(device, queue, Phase::Upload, |_| {
+            queue.write_buffer(&params_buffer, 0, bytemuck::cast_slice(&params));
+        });
+
+        let mut k = 1;
+        while k < n {
+            if k != 1 {
+                profiler.phase(device, queue, Phase::Copy, |encoder| {
+                    encoder.copy_buffer_to_buffer(out_buffer, 0, in_buffer, 0, size as u64);
+                });
+            }
+            profiler.phase(device, queue, Phase::Kernel, |encoder| {
+                self.kernel.run(encoder, &bind_group, n);
+            });
+            k *= 2;
+        }
     }
 
     pub fn stage_adjacency_matrix(
@@ -167,18 +443,54 @@ impl<K: TropicalMatmulKernel> TropicalMatmul<K> {
         n: usize,
         dst: &wgpu::Buffer,
     ) {
-        stage_adjacency_matrix(
+        stage_adjacency_matrix::<K::Element>(
             device,
             queue,
             edges,
-            K::num_threads_x(n),
-            K::buffer_size(n),
+            n,
+            self.stride(n),
+            self.stride(n),
+            dst,
+        );
+    }
+
+    // Keeps the weights and direction of the edges of `graph`.
+    pub fn stage_graph(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        graph: &Graph,
+        dst: &wgpu::Buffer,
+    ) {
+        stage_graph::<K::Element>(
+            device,
+            queue,
+            graph,
+            self.stride(graph.n),
+            self.stride(graph.n),
+            dst,
+        );
+    }
+
+    pub fn stage_distance_matrix(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        matrix: &DistanceMatrix<K::Element>,
+        dst: &wgpu::Buffer,
+    ) {
+        stage_distance_matrix::<K::Element>(
+            device,
+            queue,
+            matrix,
+            self.stride(matrix.n()),
+            self.stride(matrix.n()),
             dst,
         );
     }
 
     pub fn create_buffer(&self, device: &wgpu::Device, n: usize) -> (wgpu::Buffer, wgpu::Buffer) {
-        let size = K::buffer_size(n);
+        let size = self.kernel.buffer_size(n);
         let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
             size: size as u64,
@@ -198,46 +510,28 @@ impl<K: TropicalMatmulKernel> TropicalMatmul<K> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::init;
 
-    async fn init() -> (wgpu::Device, wgpu::Queue) {
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
-            .await
-            .unwrap();
-        adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::downlevel_defaults(),
-                },
-                None,
-            )
-            .await
-            .unwrap()
-    }
-
-    async fn test_tropical_matmul<K: TropicalMatmulKernel>(
+    async fn test_tropical_matmul<K: TropicalMatmulKernel<Element = f32>>(
         kernel: K,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) {
         let n = 100usize;
         let tm = TropicalMatmul::new(kernel);
-        let (in_buffer, out_buffer) = tm.create_buffer(&device, n);
+        let (in_buffer, out_buffer) = tm.create_buffer(device, n);
 
         let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
-        tm.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
-        tm.run(&device, &queue, &in_buffer, &out_buffer, n);
-        let result = crate::buffer::download_distance_matrix(&device, &queue, &out_buffer)
+        tm.stage_adjacency_matrix(device, queue, &edges, n, &in_buffer);
+        tm.run(device, queue, &in_buffer, &out_buffer, n);
+        let result = crate::buffer::download_distance_matrix(device, queue, &out_buffer)
             .await
             .unwrap();
 
         for i in 0..n {
             for j in 0..n {
                 let d = (i as f32 - j as f32).abs();
-                assert_eq!(result[i * K::num_threads_x(n) + j], d);
+                assert_eq!(result[i * tm.stride(n) + j], d);
             }
         }
     }
@@ -245,12 +539,206 @@ mod tests {
     #[tokio::test]
     async fn test_tropical_matmul_naive() {
         let (device, queue) = init().await;
-        test_tropical_matmul(NaiveTropicalMatmulKernel::new(&device), &device, &queue).await;
+        test_tropical_matmul(
+            NaiveTropicalMatmulKernel::<f32>::new(&device),
+            &device,
+            &queue,
+        )
+        .await;
     }
 
     #[tokio::test]
     async fn test_tropical_matmul_block() {
         let (device, queue) = init().await;
-        test_tropical_matmul(BlockedTropicalMatmulKernel::new(&device), &device, &queue).await;
+        test_tropical_matmul(
+            BlockedTropicalMatmulKernel::<f32>::new(&device),
+            &device,
+            &queue,
+        )
+        .await;
+    }
+
+    async fn test_tropical_matmul_element<K: TropicalMatmulKernel>(
+        kernel: K,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        use rand::{Rng, SeedableRng};
+
+        let n = 45;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let edges = (0..n)
+            .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+            .filter(|_| rng.gen_bool(0.05))
+            .collect::<Vec<_>>();
+        let tm = TropicalMatmul::new(kernel);
+        let (in_buffer, out_buffer) = tm.create_buffer(device, n);
+        tm.stage_adjacency_matrix(device, queue, &edges, n, &in_buffer);
+        tm.run(device, queue, &in_buffer, &out_buffer, n);
+        let result = crate::buffer::download_buffer::<K::Element>(device, queue, &out_buffer)
+            .await
+            .unwrap();
+
+        let expected = crate::cpu::warshall_floyd::<K::Element>(&edges, n);
+        let stride = tm.stride(n);
+        for i in 0..n {
+            assert_eq!(
+                result[i * stride..i * stride + n],
+                expected[i * n..(i + 1) * n]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_workspace() {
+        let (device, queue) = init().await;
+        let n = 60;
+        let tm = TropicalMatmul::new(RegisterTiledTropicalMatmulKernel::new(&device));
+        let workspace = tm.create_workspace(&device, n);
+        for edges in [
+            (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>(),
+            (0..n).map(|i| (i, (i * 7 + 3) % n)).collect::<Vec<_>>(),
+        ] {
+            tm.stage_adjacency_matrix(&device, &queue, &edges, n, workspace.in_buffer());
+            tm.run_workspace(&device, &queue, &workspace);
+            let result =
+                crate::buffer::download_buffer::<f32>(&device, &queue, workspace.out_buffer())
+                    .await
+                    .unwrap();
+            let expected = crate::cpu::warshall_floyd::<f32>(&edges, n);
+            let stride = tm.stride(n);
+            for i in 0..n {
+                assert_eq!(
+                    result[i * stride..i * stride + n],
+                    expected[i * n..(i + 1) * n]
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_profiled() {
+        let (device, queue) = init().await;
+        let n = 100;
+        let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
+        let tm = TropicalMatmul::new(BlockedTropicalMatmulKernel::<f32>::new(&device));
+        let (in_buffer, out_buffer) = tm.create_buffer(&device, n);
+        tm.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        let mut profiler = crate::profile::Profiler::new(&device);
+        tm.run_profiled(&device, &queue, &in_buffer, &out_buffer, n, &mut profiler);
+        let result = crate::buffer::download_buffer::<f32>(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
+        let profile = profiler.finish(&device, &queue).await;
+
+        let expected = crate::cpu::warshall_floyd::<f32>(&edges, n);
+        let stride = tm.stride(n);
+        for i in 0..n {
+            assert_eq!(
+                result[i * stride..i * stride + n],
+                expected[i * n..(i + 1) * n]
+            );
+        }
+        let kernel = profile.phase(crate::profile::Phase::Kernel).unwrap();
+        assert_eq!(kernel.count, 7);
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_max_n() {
+        let (device, _) = init().await;
+        let limits = device.limits();
+        let max_size = limits.max_storage_buffer_binding_size as usize;
+        let tm = TropicalMatmul::new(NaiveTropicalMatmulKernel::<f32>::new(&device));
+        let n = tm.max_n(&limits);
+        assert!(tm.kernel.buffer_size(n) <= max_size);
+        assert!(tm.kernel.buffer_size(n + 1) > max_size);
+        let tm = TropicalMatmul::new(BlockedTropicalMatmulKernel::<f32>::new(&device));
+        let n = tm.max_n(&limits);
+        assert!(tm.kernel.buffer_size(n) <= max_size);
+        assert!(tm.kernel.buffer_size(n + 1) > max_size);
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_tile_size() {
+        let (device, queue) = init().await;
+        for size in [4, 8] {
+            let kernel = NaiveTropicalMatmulKernel::<u32>::with_workgroup_size(&device, size, size);
+            test_tropical_matmul_element(kernel, &device, &queue).await;
+            let kernel = BlockedTropicalMatmulKernel::<u16>::with_tile_size(&device, size);
+            test_tropical_matmul_element(kernel, &device, &queue).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_register() {
+        let (device, queue) = init().await;
+        let kernel = RegisterTiledTropicalMatmulKernel::new(&device);
+        test_tropical_matmul(kernel, &device, &queue).await;
+        let kernel = RegisterTiledTropicalMatmulKernel::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+        let kernel = RegisterTiledTropicalMatmulKernel::with_micro_tile_size(&device, 8);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_cancellable() {
+        let (device, queue) = init().await;
+        let n = 100;
+        let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
+        let tm = TropicalMatmul::new(NaiveTropicalMatmulKernel::<f32>::new(&device));
+        let (in_buffer, out_buffer) = tm.create_buffer(&device, n);
+        tm.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        let mut reported = vec![];
+        tm.run_cancellable(
+            &device,
+            &queue,
+            &in_buffer,
+            &out_buffer,
+            n,
+            &CancellationToken::new(),
+            |progress| reported.push((progress.completed, progress.total)),
+        )
+        .unwrap();
+        assert_eq!(reported, (1..=7).map(|i| (i, 7)).collect::<Vec<_>>());
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = tm.run_cancellable(
+            &device,
+            &queue,
+            &in_buffer,
+            &out_buffer,
+            n,
+            &token,
+            |_| unreachable!(),
+        );
+        assert_eq!(result, Err(Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_u32() {
+        let (device, queue) = init().await;
+        let kernel = NaiveTropicalMatmulKernel::<u32>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+        let kernel = BlockedTropicalMatmulKernel::<u32>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_u16() {
+        let (device, queue) = init().await;
+        let kernel = NaiveTropicalMatmulKernel::<u16>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+        let kernel = BlockedTropicalMatmulKernel::<u16>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+    }
+
+    #[tokio::test]
+    async fn test_tropical_matmul_f16() {
+        let (device, queue) = init().await;
+        let kernel = NaiveTropicalMatmulKernel::<half::f16>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
+        let kernel = BlockedTropicalMatmulKernel::<half::f16>::new(&device);
+        test_tropical_matmul_element(kernel, &device, &queue).await;
     }
 }
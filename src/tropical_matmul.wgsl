@@ -5,31 +5,32 @@ struct Params {
 
 @group(0)
 @binding(0)
-var<storage, read> buffer_in: array<f32>;
+var<storage, read> buffer_in: array<Word>;
 @group(0)
 @binding(1)
-var<storage, write> buffer_out: array<f32>;
+var<storage, write> buffer_out: array<Word>;
 @group(0)
 @binding(2)
 var<uniform> params: Params;
 
 @compute
-@workgroup_size(1, 1)
+@workgroup_size({X}, {Y})
 fn tropical_matmul(
   @builtin(global_invocation_id) global_invocation_id : vec3<u32>,
 ) {
   var x : u32 = global_invocation_id.x;
   var y : u32 = global_invocation_id.y;
-  var s : f32 = 1000.;
+  var s : Lanes = infinity();
   var k : u32 = 0u;
   var n : u32 = params.n;
-  var stride : u32 = params.stride;
+  var words : u32 = params.stride / LANES;
   loop {
     if (k >= n) {
       break;
     }
-    s = min(s, buffer_in[y * n + k] + buffer_in[k * n + x]);
+    var a : Scalar = element(buffer_in[y * words + k / LANES], k % LANES);
+    s = min(s, add(splat(a), unpack(buffer_in[k * words + x])));
     k = k + 1u;
   }
-  buffer_out[y * n + x] = s;
+  buffer_out[y * words + x] = pack(s);
 }
@@ -5,19 +5,19 @@ struct Params {
 
 @group(0)
 @binding(0)
-var<storage, read> buffer_in: array<f32>;
+var<storage, read> buffer_in: array<Word>;
 @group(0)
 @binding(1)
-var<storage, write> buffer_out: array<f32>;
+var<storage, write> buffer_out: array<Word>;
 @group(0)
 @binding(2)
 var<uniform> params: Params;
 
-var<workgroup> a_local : array<f32, 256>;
-var<workgroup> b_local : array<f32, 256>;
+var<workgroup> a_local : array<Scalar, {TILE2}>;
+var<workgroup> b_local : array<Lanes, {TILE2}>;
 
 @compute
-@workgroup_size(16, 16)
+@workgroup_size({TILE}, {TILE})
 fn tropical_matmul(
   @builtin(global_invocation_id) global_invocation_id : vec3<u32>,
   @builtin(local_invocation_id) local_invocation_id : vec3<u32>,
@@ -29,27 +29,29 @@ fn tropical_matmul(
   var y_local : u32 = local_invocation_id.y;
   var n : u32 = params.n;
   var stride : u32 = params.stride;
+  var words : u32 = stride / LANES;
 
-  var s : f32 = 1000000.;
+  var s : Lanes = infinity();
   var k : u32 = 0u;
 
   loop {
-    if (16u * k >= stride) {
+    if ({TILE}u * k >= stride) {
       break;
     }
     workgroupBarrier();
-    a_local[y_local * 16u + x_local] = buffer_in[(16u * workgroup_id.y + y_local) * stride + (16u * k + x_local)];
-    b_local[y_local * 16u + x_local] = buffer_in[(16u * k + y_local) * stride + (16u * workgroup_id.x + x_local)];
+    var a : u32 = {TILE}u * k + x_local;
+    a_local[y_local * {TILE}u + x_local] = element(buffer_in[y * words + a / LANES], a % LANES);
+    b_local[y_local * {TILE}u + x_local] = unpack(buffer_in[({TILE}u * k + y_local) * words + x]);
     workgroupBarrier();
     var z : u32 = 0u;
     loop {
-      if (z >= 16u) {
+      if (z >= {TILE}u) {
         break;
       }
-      s = min(s, a_local[y_local * 16u + z] + b_local[z * 16u + x_local]);
+      s = min(s, add(splat(a_local[y_local * {TILE}u + z]), b_local[z * {TILE}u + x_local]));
       z = z + 1u;
     }
     k = k + 1u;
   }
-  buffer_out[y * stride + x] = s;
+  buffer_out[y * words + x] = pack(s);
 }
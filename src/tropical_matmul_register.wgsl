@@ -0,0 +1,112 @@
+struct Params {
+  n : u32,
+  stride : u32,
+};
+
+@group(0)
+@binding(0)
+var<storage, read> buffer_in: array<vec4<f32>>;
+@group(0)
+@binding(1)
+var<storage, write> buffer_out: array<vec4<f32>>;
+@group(0)
+@binding(2)
+var<uniform> params: Params;
+
+// Each workgroup computes a {TILE}x{TILE} output tile in steps of 16 along k.
+// Thread (tx, ty) owns the {M}x{M} micro-tile starting at (ty * {M}, tx * {M}).
+var<workgroup> a_local : array<f32, {TILE_K}>;
+var<workgroup> b_local : array<vec4<f32>, {TILE_K4}>;
+
+@compute
+@workgroup_size(16, 16)
+fn tropical_matmul(
+  @builtin(local_invocation_id) local_invocation_id : vec3<u32>,
+  @builtin(local_invocation_index) local_index : u32,
+  @builtin(workgroup_id) workgroup_id : vec3<u32>,
+) {
+  var tx : u32 = local_invocation_id.x;
+  var ty : u32 = local_invocation_id.y;
+  var row0 : u32 = {TILE}u * workgroup_id.y;
+  var col0 : u32 = {TILE}u * workgroup_id.x;
+  var stride : u32 = params.stride;
+  var words : u32 = stride / 4u;
+
+  var inf : f32 = bitcast<f32>(0x7f800000u);
+  var acc : array<vec4<f32>, {ACC}>;
+  var i : u32 = 0u;
+  loop {
+    if (i >= {ACC}u) {
+      break;
+    }
+    acc[i] = vec4<f32>(inf);
+    i = i + 1u;
+  }
+
+  var k0 : u32 = 0u;
+  loop {
+    if (k0 >= stride) {
+      break;
+    }
+    workgroupBarrier();
+    var l : u32 = 0u;
+    loop {
+      if (l >= {LOADS}u) {
+        break;
+      }
+      var index : u32 = l * 256u + local_index;
+      var r : u32 = index / 4u;
+      var c : u32 = index % 4u;
+      var a : vec4<f32> = buffer_in[(row0 + r) * words + k0 / 4u + c];
+      a_local[r * 16u + c * 4u] = a.x;
+      a_local[r * 16u + c * 4u + 1u] = a.y;
+      a_local[r * 16u + c * 4u + 2u] = a.z;
+      a_local[r * 16u + c * 4u + 3u] = a.w;
+      r = index / {TILE4}u;
+      c = index % {TILE4}u;
+      b_local[index] = buffer_in[(k0 + r) * words + col0 / 4u + c];
+      l = l + 1u;
+    }
+    workgroupBarrier();
+    var z : u32 = 0u;
+    loop {
+      if (z >= 16u) {
+        break;
+      }
+      i = 0u;
+      loop {
+        if (i >= {M}u) {
+          break;
+        }
+        var a : vec4<f32> = vec4<f32>(a_local[(ty * {M}u + i) * 16u + z]);
+        var j : u32 = 0u;
+        loop {
+          if (j >= {M4}u) {
+            break;
+          }
+          acc[i * {M4}u + j] = min(acc[i * {M4}u + j], a + b_local[z * {TILE4}u + tx * {M4}u + j]);
+          j = j + 1u;
+        }
+        i = i + 1u;
+      }
+      z = z + 1u;
+    }
+    k0 = k0 + 16u;
+  }
+
+  i = 0u;
+  loop {
+    if (i >= {M}u) {
+      break;
+    }
+    var j : u32 = 0u;
+    loop {
+      if (j >= {M4}u) {
+        break;
+      }
+      buffer_out[(row0 + ty * {M}u + i) * words + col0 / 4u + tx * {M4}u + j] = acc[i * {M4}u + j];
+      j = j + 1u;
+    }
+    i = i + 1u;
+  }
+}
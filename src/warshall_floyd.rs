@@ -1,20 +1,49 @@
+use crate::{
+    distance::{create_shader_module, Distance},
+    graph::Graph,
+    kernel::render_template,
+    matrix::DistanceMatrix,
+    poller::Poller,
+    profile::{Phase, Profiler},
+    progress::{CancellationToken, Cancelled, Progress, Tracker},
+    workspace::Workspace,
+};
+use std::marker::PhantomData;
+
 pub struct WarshallFloydKernel {
     pipeline: wgpu::ComputePipeline,
+    workgroup_size: [usize; 2],
+    lanes: usize,
 }
 
 impl WarshallFloydKernel {
+    // Rows are padded to the default workgroup size regardless of the size
+    // the kernel is dispatched with, so the matrix layout never changes.
     const WORKGROUP_SIZE_X: usize = 16;
     const WORKGROUP_SIZE_Y: usize = 16;
 
-    pub fn new(device: &wgpu::Device) -> WarshallFloydKernel {
-        let module = device.create_shader_module(wgpu::include_wgsl!("warshall_floyd.wgsl"));
+    pub fn new<T: Distance>(device: &wgpu::Device) -> WarshallFloydKernel {
+        Self::with_workgroup_size::<T>(device, Self::WORKGROUP_SIZE_X, Self::WORKGROUP_SIZE_Y)
+    }
+
+    pub fn with_workgroup_size<T: Distance>(
+        device: &wgpu::Device,
+        x: usize,
+        y: usize,
+    ) -> WarshallFloydKernel {
+        let source = render_template(include_str!("warshall_floyd.wgsl"), &[("X", x), ("Y", y)]);
+        let module = create_shader_module::<T>(device, &source);
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: None,
             module: &module,
             layout: None,
             entry_point: "warshall_floyd",
         });
-        WarshallFloydKernel { pipeline }
+        WarshallFloydKernel {
+            pipeline,
+            workgroup_size: [x, y],
+            lanes: T::LANES,
+        }
     }
 
     pub fn bind(
@@ -50,18 +79,18 @@ impl WarshallFloydKernel {
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, bind_group, &[]);
         pass.dispatch_workgroups(
-            Self::num_workgroups_x(n) as u32,
-            Self::num_workgroups_y(n) as u32,
+            (Self::stride_x(n) / self.lanes).div_ceil(self.workgroup_size[0]) as u32,
+            Self::stride_y(n).div_ceil(self.workgroup_size[1]) as u32,
             1,
         );
     }
 
     pub fn num_workgroups_x(n: usize) -> usize {
-        (n + Self::WORKGROUP_SIZE_X - 1) / Self::WORKGROUP_SIZE_X
+        n.div_ceil(Self::WORKGROUP_SIZE_X)
     }
 
     pub fn num_workgroups_y(n: usize) -> usize {
-        (n + Self::WORKGROUP_SIZE_Y - 1) / Self::WORKGROUP_SIZE_Y
+        n.div_ceil(Self::WORKGROUP_SIZE_Y)
     }
 
     pub fn stride_x(n: usize) -> usize {
@@ -71,20 +100,42 @@ impl WarshallFloydKernel {
     pub fn stride_y(n: usize) -> usize {
         Self::num_workgroups_y(n) * Self::WORKGROUP_SIZE_Y
     }
-
-    pub fn buffer_size(n: usize) -> usize {
-        Self::stride_x(n) * n * 4
-    }
 }
 
-pub struct WarshallFloyd {
+pub struct WarshallFloyd<T: Distance = f32> {
     kernel: WarshallFloydKernel,
+    element: PhantomData<T>,
 }
 
-impl WarshallFloyd {
-    pub fn new(device: &wgpu::Device) -> WarshallFloyd {
-        let kernel = WarshallFloydKernel::new(device);
-        WarshallFloyd { kernel }
+impl<T: Distance> WarshallFloyd<T> {
+    pub fn new(device: &wgpu::Device) -> WarshallFloyd<T> {
+        let kernel = WarshallFloydKernel::new::<T>(device);
+        WarshallFloyd {
+            kernel,
+            element: PhantomData,
+        }
+    }
+
+    pub fn with_workgroup_size(device: &wgpu::Device, x: usize, y: usize) -> WarshallFloyd<T> {
+        let kernel = WarshallFloydKernel::with_workgroup_size::<T>(device, x, y);
+        WarshallFloyd {
+            kernel,
+            element: PhantomData,
+        }
+    }
+
+    pub fn buffer_size(n: usize) -> usize {
+        WarshallFloydKernel::stride_x(n) * n * std::mem::size_of::<T>()
+    }
+
+    // Largest n whose matrix fits in a single storage binding.
+    pub fn max_n(limits: &wgpu::Limits) -> usize {
+        let max_size = limits.max_storage_buffer_binding_size as usize;
+        let mut n = ((max_size / std::mem::size_of::<T>()) as f64).sqrt() as usize;
+        while n > 0 && Self::buffer_size(n) > max_size {
+            n -= 1;
+        }
+        n
     }
 
     pub fn run(
@@ -95,7 +146,40 @@ impl WarshallFloyd {
         out_buffer: &wgpu::Buffer,
         n: usize,
     ) {
-        let size = WarshallFloydKernel::buffer_size(n);
+        self.run_passes(device, queue, in_buffer, out_buffer, n, None)
+            .unwrap();
+    }
+
+    // Same as `run`, but reports every finished pivot to `progress` and stops
+    // before the next submission once `token` is cancelled. Waits for the
+    // device before returning.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_cancellable<F: FnMut(Progress)>(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        token: &CancellationToken,
+        mut progress: F,
+    ) -> Result<(), Cancelled> {
+        let mut tracker = Tracker::new(device, token, &mut progress, n);
+        self.run_passes(device, queue, in_buffer, out_buffer, n, Some(&mut tracker))?;
+        tracker.finish();
+        Ok(())
+    }
+
+    fn run_passes(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        mut tracker: Option<&mut Tracker>,
+    ) -> Result<(), Cancelled> {
+        let size = Self::buffer_size(n);
         let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
             size: 8,
@@ -104,10 +188,13 @@ impl WarshallFloyd {
         });
         let bind_group = self
             .kernel
-            .bind(&device, &in_buffer, &out_buffer, &params_buffer);
+            .bind(device, in_buffer, out_buffer, &params_buffer);
 
         let mut params_buffer_staging = wgpu::util::StagingBelt::new(8);
         for k in 0..n {
+            if let Some(tracker) = tracker.as_mut() {
+                tracker.check()?;
+            }
             let mut encoder =
                 device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
             let params = vec![WarshallFloydKernel::stride_x(n) as u32, k as u32];
@@ -117,17 +204,184 @@ impl WarshallFloyd {
                     &params_buffer,
                     0,
                     std::num::NonZeroU64::new(8).unwrap(),
-                    &device,
+                    device,
                 )
                 .copy_from_slice(bytemuck::cast_slice(&params));
             params_buffer_staging.finish();
             if k > 0 {
-                encoder.copy_buffer_to_buffer(&out_buffer, 0, &in_buffer, 0, size as u64);
+                encoder.copy_buffer_to_buffer(out_buffer, 0, in_buffer, 0, size as u64);
             }
             self.kernel.run(&mut encoder, &bind_group, n);
-            queue.submit(Some(encoder.finish()));
+            let index = queue.submit(Some(encoder.finish()));
+            if let Some(tracker) = tracker.as_mut() {
+                tracker.submitted(index);
+            }
             params_buffer_staging.recall();
         }
+        Ok(())
+    }
+
+    pub fn create_workspace(&self, device: &wgpu::Device, n: usize) -> Workspace {
+        let stride = WarshallFloydKernel::stride_x(n) as u32;
+        let iteration_params = (0..n as u32).map(|k| [stride, k]).collect::<Vec<_>>();
+        Workspace::new(
+            device,
+            n,
+            Self::buffer_size(n),
+            &iteration_params,
+            |in_buffer, out_buffer, params_buffer| {
+                self.kernel
+                    .bind(device, in_buffer, out_buffer, params_buffer)
+            },
+        )
+    }
+
+    // Runs on the matrix staged in `workspace.in_buffer()` with a single
+    // submission. The input buffer is overwritten.
+    pub fn run_workspace(&self, device: &wgpu::Device, queue: &wgpu::Queue, workspace: &Workspace) {
+        let n = workspace.n();
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for k in 0..n {
+            workspace.encode_params(&mut encoder, k);
+            if k > 0 {
+                workspace.encode_feedback(&mut encoder);
+            }
+            self.kernel.run(&mut encoder, workspace.bind_group(), n);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+
+    // Runs on `workspace` and resolves with the distance matrix once the
+    // device is done, without blocking the awaiting thread.
+    pub async fn run_async(
+        &self,
+        poller: &Poller,
+        queue: &wgpu::Queue,
+        workspace: &Workspace,
+    ) -> Option<Vec<T>> {
+        self.run_workspace(poller.device(), queue, workspace);
+        poller.download_buffer(queue, workspace.out_buffer()).await
+    }
+
+    // Same as `run`, but submits every phase separately and records it in
+    // `profiler`.
+    pub fn run_profiled(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        in_buffer: &wgpu::Buffer,
+        out_buffer: &wgpu::Buffer,
+        n: usize,
+        profiler: &mut Profiler,
+    ) {
+        let size = Self::buffer_size(n);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self
+            .kernel
+            .bind(device, in_buffer, out_buffer, &params_buffer);
+
+        let mut params_buffer_staging = wgpu::util::StagingBelt::new(8);
+        for k in 0..n {
+            profiler.phase(device, queue, Phase::Upload, |encoder| {
+                let params = vec![WarshallFloydKernel::stride_x(n) as u32, k as u32];
+                params_buffer_staging
+                    .write_buffer(
+                        encoder,
+                        &params_buffer,
+                        0,
+                        std::num::NonZeroU64::new(8).unwrap(),
+                        device,
+                    )
+                    .copy_from_slice(bytemuck::cast_slice(&params));
+                params_buffer_staging.finish();
+            });
+            params_buffer_staging.recall();
+            if k > 0 {
+                profiler.phase(device, queue, Phase::Copy, |encoder| {
+                    encoder.copy_buffer_to_buffer(out_buffer, 0, in_buffer, 0, size as u64);
+                });
+            }
+            profiler.phase(device, queue, Phase::Kernel, |encoder| {
+                self.kernel.run(encoder, &bind_group, n);
+            });
+        }
+    }
+
+    pub fn stage_adjacency_matrix(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        edges: &[(usize, usize)],
+        n: usize,
+        dst: &wgpu::Buffer,
+    ) {
+        crate::buffer::stage_adjacency_matrix::<T>(
+            device,
+            queue,
+            edges,
+            n,
+            WarshallFloydKernel::stride_x(n),
+            n,
+            dst,
+        );
+    }
+
+    // Keeps the weights and direction of the edges of `graph`.
+    pub fn stage_graph(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        graph: &Graph,
+        dst: &wgpu::Buffer,
+    ) {
+        crate::buffer::stage_graph::<T>(
+            device,
+            queue,
+            graph,
+            WarshallFloydKernel::stride_x(graph.n),
+            graph.n,
+            dst,
+        );
+    }
+
+    pub fn stage_distance_matrix(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        matrix: &DistanceMatrix<T>,
+        dst: &wgpu::Buffer,
+    ) {
+        crate::buffer::stage_distance_matrix::<T>(
+            device,
+            queue,
+            matrix,
+            WarshallFloydKernel::stride_x(matrix.n()),
+            matrix.n(),
+            dst,
+        );
+    }
+
+    pub fn create_buffer(&self, device: &wgpu::Device, n: usize) -> (wgpu::Buffer, wgpu::Buffer) {
+        let size = Self::buffer_size(n);
+        let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        (in_buffer, out_buffer)
     }
 }
 
@@ -138,31 +392,15 @@ pub fn stage_adjacency_matrix(
     n: usize,
     dst: &wgpu::Buffer,
 ) {
-    let size = WarshallFloydKernel::buffer_size(n);
-    let stride = WarshallFloydKernel::stride_x(n);
-    let mut distance = vec![std::f32::INFINITY; stride * n];
-    for i in 0..n {
-        distance[i * stride + i] = 0.;
-    }
-    for &(i, j) in edges.iter() {
-        distance[i * stride + j] = 1.;
-        distance[j * stride + i] = 1.;
-    }
-
-    let mut encoder =
-        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-    let mut staging = wgpu::util::StagingBelt::new(size as u64);
-    staging
-        .write_buffer(
-            &mut encoder,
-            &dst,
-            0,
-            std::num::NonZeroU64::new(size as u64).unwrap(),
-            &device,
-        )
-        .copy_from_slice(bytemuck::cast_slice(&distance));
-    staging.finish();
-    queue.submit(Some(encoder.finish()));
+    crate::buffer::stage_adjacency_matrix::<f32>(
+        device,
+        queue,
+        edges,
+        n,
+        WarshallFloydKernel::stride_x(n),
+        n,
+        dst,
+    );
 }
 
 pub async fn download_distance_matrix(
@@ -170,61 +408,322 @@ pub async fn download_distance_matrix(
     queue: &wgpu::Queue,
     src: &wgpu::Buffer,
 ) -> Option<Vec<f32>> {
-    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-    wgpu::util::DownloadBuffer::read_buffer(&device, &queue, &src.slice(..), move |result| {
-        let buffer = result.unwrap();
-        let result = bytemuck::cast_slice::<u8, f32>(&buffer).to_vec();
-        sender.send(result).ok();
-    });
-    device.poll(wgpu::Maintain::Wait);
-    receiver.receive().await
+    crate::buffer::download_distance_matrix(device, queue, src).await
 }
 
-#[tokio::test]
-async fn test_warshall_floyd() {
-    let instance = wgpu::Instance::new(wgpu::Backends::all());
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions::default())
-        .await
-        .unwrap();
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::downlevel_defaults(),
-            },
-            None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_warshall_floyd() {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .unwrap();
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: wgpu::Features::empty(),
+                    limits: wgpu::Limits::downlevel_defaults(),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let n = 1000usize;
+        let size = WarshallFloyd::<f32>::buffer_size(n);
+        let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
+        stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        WarshallFloyd::<f32>::new(&device).run(&device, &queue, &in_buffer, &out_buffer, n);
+        let result = download_distance_matrix(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
+
+        for i in 0..n {
+            for j in 0..n {
+                let d = (i as f32 - j as f32).abs();
+                assert_eq!(result[i * WarshallFloydKernel::stride_x(n) + j], d);
+            }
+        }
+    }
+
+    async fn test_warshall_floyd_element<T: Distance>() {
+        use rand::{Rng, SeedableRng};
+
+        let (device, queue) = crate::testing::init().await;
+        let n = 45;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let edges = (0..n)
+            .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+            .filter(|_| rng.gen_bool(0.05))
+            .collect::<Vec<_>>();
+        let wf = WarshallFloyd::<T>::new(&device);
+        let (in_buffer, out_buffer) = wf.create_buffer(&device, n);
+        wf.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        wf.run(&device, &queue, &in_buffer, &out_buffer, n);
+        let result = crate::buffer::download_buffer::<T>(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
+
+        let expected = crate::cpu::warshall_floyd::<T>(&edges, n);
+        let stride = WarshallFloydKernel::stride_x(n);
+        for i in 0..n {
+            assert_eq!(
+                result[i * stride..i * stride + n],
+                expected[i * n..(i + 1) * n]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_u32() {
+        test_warshall_floyd_element::<u32>().await;
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_u16() {
+        test_warshall_floyd_element::<u16>().await;
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_f16() {
+        test_warshall_floyd_element::<half::f16>().await;
+    }
+
+    #[test]
+    fn test_max_n() {
+        let limits = wgpu::Limits::downlevel_defaults();
+        let n32 = WarshallFloyd::<f32>::max_n(&limits);
+        let n16 = WarshallFloyd::<half::f16>::max_n(&limits);
+        assert!(
+            WarshallFloyd::<f32>::buffer_size(n32)
+                <= limits.max_storage_buffer_binding_size as usize
+        );
+        assert!(
+            WarshallFloyd::<f32>::buffer_size(n32 + 1)
+                > limits.max_storage_buffer_binding_size as usize
+        );
+        // Half the bytes per element, so about sqrt(2) times the vertices.
+        assert!(n16 * 10 > n32 * 14);
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_profiled() {
+        let (device, queue) = crate::testing::init().await;
+        let n = 50;
+        let edges = crate::testing::path_graph(n);
+        let wf = WarshallFloyd::<f32>::new(&device);
+        let (in_buffer, out_buffer) = wf.create_buffer(&device, n);
+        let mut profiler = Profiler::new(&device);
+        profiler.measure(&device, Phase::Staging, || {
+            wf.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer)
+        });
+        wf.run_profiled(&device, &queue, &in_buffer, &out_buffer, n, &mut profiler);
+        let start = std::time::Instant::now();
+        let result = download_distance_matrix(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
+        profiler.record(Phase::Download, start.elapsed());
+        let profile = profiler.finish(&device, &queue).await;
+
+        let expected = crate::cpu::warshall_floyd::<f32>(&edges, n);
+        let stride = WarshallFloydKernel::stride_x(n);
+        for i in 0..n {
+            assert_eq!(
+                result[i * stride..i * stride + n],
+                expected[i * n..(i + 1) * n]
+            );
+        }
+        let phases = profile.phases.iter().map(|p| p.phase).collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            [
+                Phase::Staging,
+                Phase::Upload,
+                Phase::Copy,
+                Phase::Kernel,
+                Phase::Download
+            ]
+        );
+        assert_eq!(profile.phase(Phase::Kernel).unwrap().count, n);
+        assert_eq!(profile.phase(Phase::Copy).unwrap().count, n - 1);
+        assert!(profile.to_json().contains("\"phase\":\"kernel\""));
+        assert!(profile.to_string().contains("kernel"));
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_workspace() {
+        let (device, queue) = crate::testing::init().await;
+        let n = 40;
+        let wf = WarshallFloyd::<u32>::new(&device);
+        let stride = WarshallFloydKernel::stride_x(n);
+        // The driver is shared, every thread runs on its own workspace.
+        std::thread::scope(|scope| {
+            let threads = (0..4)
+                .map(|seed| {
+                    let (device, queue, wf) = (&device, &queue, &wf);
+                    let workspace = wf.create_workspace(device, n);
+                    scope.spawn(move || {
+                        use rand::{Rng, SeedableRng};
+
+                        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                        let edges = (0..n)
+                            .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+                            .filter(|_| rng.gen_bool(0.08))
+                            .collect::<Vec<_>>();
+                        for _ in 0..3 {
+                            wf.stage_adjacency_matrix(
+                                device,
+                                queue,
+                                &edges,
+                                n,
+                                workspace.in_buffer(),
+                            );
+                            wf.run_workspace(device, queue, &workspace);
+                            let result = pollster::block_on(crate::buffer::download_buffer::<u32>(
+                                device,
+                                queue,
+                                workspace.out_buffer(),
+                            ))
+                            .unwrap();
+                            let expected = crate::cpu::warshall_floyd::<u32>(&edges, n);
+                            for i in 0..n {
+                                assert_eq!(
+                                    result[i * stride..i * stride + n],
+                                    expected[i * n..(i + 1) * n]
+                                );
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_cancellable() {
+        let (device, queue) = crate::testing::init().await;
+        let n = 50;
+        let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
+        let wf = WarshallFloyd::<f32>::new(&device);
+        let (in_buffer, out_buffer) = wf.create_buffer(&device, n);
+
+        wf.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        let mut reported = vec![];
+        let token = CancellationToken::new();
+        wf.run_cancellable(
+            &device,
+            &queue,
+            &in_buffer,
+            &out_buffer,
+            n,
+            &token,
+            |progress| reported.push(progress),
         )
-        .await
         .unwrap();
+        assert_eq!(reported.len(), n);
+        for (k, progress) in reported.iter().enumerate() {
+            assert_eq!(progress.completed, k + 1);
+            assert_eq!(progress.total, n);
+        }
+        let result = download_distance_matrix(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
+        assert_eq!(result[n - 1], (n - 1) as f32);
 
-    let n = 1000usize;
-    let size = WarshallFloydKernel::buffer_size(n);
-    let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        size: size as u64,
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
-    let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        size: size as u64,
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-        mapped_at_creation: false,
-    });
-
-    let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
-    stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
-    WarshallFloyd::new(&device).run(&device, &queue, &in_buffer, &out_buffer, n);
-    let result = download_distance_matrix(&device, &queue, &out_buffer)
-        .await
-        .unwrap();
+        wf.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        let mut reported = 0;
+        let result = wf.run_cancellable(
+            &device,
+            &queue,
+            &in_buffer,
+            &out_buffer,
+            n,
+            &token.clone(),
+            |progress| {
+                reported = progress.completed;
+                if progress.completed == 5 {
+                    token.cancel();
+                }
+            },
+        );
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(reported, 5);
+    }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_graph() {
+        let (device, queue) = crate::testing::init().await;
+        let n = 30;
+        let edges = (0..n).map(|i| (i, (i * 7 + 3) % n)).collect::<Vec<_>>();
+        let graph = Graph {
+            n,
+            weights: Some((0..n).map(|i| (i % 5) as f32 + 0.5).collect()),
+            edges,
+            directed: true,
+            labels: None,
+        };
+        let wf = WarshallFloyd::<u32>::new(&device);
+        let (in_buffer, out_buffer) = wf.create_buffer(&device, n);
+        wf.stage_graph(&device, &queue, &graph, &in_buffer);
+        wf.run(&device, &queue, &in_buffer, &out_buffer, n);
+        let result = crate::buffer::download_buffer::<u32>(&device, &queue, &out_buffer)
+            .await
+            .unwrap();
 
-    for i in 0..n {
-        for j in 0..n {
-            let d = (i as f32 - j as f32).abs();
-            assert_eq!(result[i * WarshallFloydKernel::stride_x(n) + j], d);
+        let expected = crate::cpu::warshall_floyd_graph::<u32>(&graph);
+        let stride = WarshallFloydKernel::stride_x(n);
+        for i in 0..n {
+            assert_eq!(
+                result[i * stride..i * stride + n],
+                expected[i * n..(i + 1) * n]
+            );
         }
     }
+
+    #[tokio::test]
+    async fn test_warshall_floyd_warm_start() {
+        let (device, queue) = crate::testing::init().await;
+        let n = 40;
+        let edges = (1..n).map(|i| (i - 1, i)).collect::<Vec<_>>();
+        let wf = WarshallFloyd::<f32>::new(&device);
+        let (in_buffer, out_buffer) = wf.create_buffer(&device, n);
+        wf.stage_adjacency_matrix(&device, &queue, &edges, n, &in_buffer);
+        wf.run(&device, &queue, &in_buffer, &out_buffer, n);
+        let stride = WarshallFloydKernel::stride_x(n);
+        let matrix = DistanceMatrix::<f32>::download(&device, &queue, &out_buffer, n, stride)
+            .await
+            .unwrap();
+
+        let mut file = vec![];
+        crate::io::write_npy(&mut file, &matrix).unwrap();
+        let matrix = crate::io::read_npy::<f32, _>(file.as_slice()).unwrap();
+        wf.stage_distance_matrix(&device, &queue, &matrix, &in_buffer);
+        wf.run(&device, &queue, &in_buffer, &out_buffer, n);
+        let result = DistanceMatrix::<f32>::download(&device, &queue, &out_buffer, n, stride)
+            .await
+            .unwrap();
+        assert_eq!(result, matrix);
+        assert_eq!(result.get(0, n - 1), (n - 1) as f32);
+    }
 }
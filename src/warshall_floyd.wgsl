@@ -5,25 +5,27 @@ struct WfParams {
 
 @group(0)
 @binding(0)
-var<storage, read> wf_buffer_in: array<f32>;
+var<storage, read> wf_buffer_in: array<Word>;
 @group(0)
 @binding(1)
-var<storage, write> wf_buffer_out: array<f32>;
+var<storage, write> wf_buffer_out: array<Word>;
 @group(0)
 @binding(2)
 var<uniform> wf_params: WfParams;
 
 @compute
-@workgroup_size(16, 16)
+@workgroup_size({X}, {Y})
 fn warshall_floyd(@builtin(global_invocation_id) global_invocation_id : vec3<u32>) {
   var x : u32 = global_invocation_id.x;
   var y : u32 = global_invocation_id.y;
   var k : u32 = wf_params.k;
   var n : u32 = wf_params.size;
-  if (x < n && y < n) {
-    wf_buffer_out[y * n + x] = min(
-      wf_buffer_in[y * n + x],
-      wf_buffer_in[y * n + k] + wf_buffer_in[k * n + x]
-    );
+  var words : u32 = n / LANES;
+  if (x < words && y < n) {
+    var d_yk : Scalar = element(wf_buffer_in[y * words + k / LANES], k % LANES);
+    wf_buffer_out[y * words + x] = pack(min(
+      unpack(wf_buffer_in[y * words + x]),
+      add(splat(d_yk), unpack(wf_buffer_in[k * words + x]))
+    ));
   }
 }
@@ -0,0 +1,103 @@
+// JavaScript bindings, built with
+// `RUSTFLAGS=--cfg=web_sys_unstable_apis wasm-pack build -- --features wasm`;
+// wgpu only enables its WebGPU backend with that flag. Edges are flat
+// `Uint32Array`s of vertex pairs and distances are returned as row-major
+// `Float32Array`s, with `Infinity` for unreachable pairs.
+use crate::{apsp, cpu, graph::Graph, matrix::DistanceMatrix};
+use wasm_bindgen::prelude::*;
+
+fn graph(
+    n: usize,
+    edges: &[u32],
+    weights: Option<Vec<f32>>,
+    directed: bool,
+) -> Result<Graph, JsError> {
+    if !edges.len().is_multiple_of(2) {
+        return Err(JsError::new("expected pairs of vertices"));
+    }
+    let graph = Graph {
+        n,
+        edges: edges
+            .chunks_exact(2)
+            .map(|e| (e[0] as usize, e[1] as usize))
+            .collect(),
+        weights,
+        directed,
+        labels: None,
+    };
+    graph.validate().map_err(|e| JsError::new(&e))?;
+    Ok(graph)
+}
+
+// Whether `navigator.gpu` exists. Node and most browsers without WebGPU
+// take the CPU path.
+#[wasm_bindgen(js_name = hasWebGpu)]
+pub fn has_webgpu() -> bool {
+    let navigator = js_sys::Reflect::get(&js_sys::global(), &"navigator".into());
+    match navigator {
+        Ok(navigator) if navigator.is_object() => js_sys::Reflect::get(&navigator, &"gpu".into())
+            .is_ok_and(|gpu| !gpu.is_undefined() && !gpu.is_null()),
+        _ => false,
+    }
+}
+
+#[wasm_bindgen(js_name = floydWarshallCpu)]
+pub fn floyd_warshall_cpu(
+    n: usize,
+    edges: &[u32],
+    weights: Option<Vec<f32>>,
+    directed: bool,
+) -> Result<Vec<f32>, JsError> {
+    let graph = graph(n, edges, weights, directed)?;
+    Ok(cpu::warshall_floyd_graph(&graph))
+}
+
+// Runs on WebGPU if available, otherwise on the CPU.
+#[wasm_bindgen(js_name = floydWarshall)]
+pub async fn floyd_warshall(
+    n: usize,
+    edges: Vec<u32>,
+    weights: Option<Vec<f32>>,
+    directed: bool,
+) -> Result<Vec<f32>, JsError> {
+    let graph = graph(n, &edges, weights, directed)?;
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    if has_webgpu() {
+        if let Some(distance) = run_gpu(&graph).await? {
+            return Ok(distance.into_vec());
+        }
+    }
+    Ok(cpu::warshall_floyd_graph(&graph))
+}
+
+// `None` if no adapter is available.
+async fn run_gpu(graph: &Graph) -> Result<Option<DistanceMatrix>, JsError> {
+    let instance = wgpu::Instance::new(wgpu::Backends::BROWSER_WEBGPU);
+    let adapter = match instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+    {
+        Some(adapter) => adapter,
+        None => return Ok(None),
+    };
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: adapter.limits(),
+            },
+            None,
+        )
+        .await
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let (buffer, stride) = apsp::run(&device, &queue, graph, apsp::Algorithm::WarshallFloyd)
+        .await
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let distance = DistanceMatrix::download(&device, &queue, &buffer, graph.n, stride)
+        .await
+        .ok_or_else(|| JsError::new("failed to download the distance matrix"))?;
+    Ok(Some(distance))
+}
@@ -0,0 +1,96 @@
+use wgpu::util::DeviceExt;
+
+// Buffers and bind group for repeated runs on graphs with the same number of
+// vertices. The uniform parameters of every iteration are uploaded once and
+// copied into place while encoding, so running allocates nothing. A workspace
+// only fits the driver that created it, and concurrent runs each need their
+// own.
+pub struct Workspace {
+    n: usize,
+    size: u64,
+    in_buffer: wgpu::Buffer,
+    out_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    iteration_params_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Workspace {
+    pub(crate) fn new<F>(
+        device: &wgpu::Device,
+        n: usize,
+        size: usize,
+        iteration_params: &[[u32; 2]],
+        bind: F,
+    ) -> Workspace
+    where
+        F: FnOnce(&wgpu::Buffer, &wgpu::Buffer, &wgpu::Buffer) -> wgpu::BindGroup,
+    {
+        let in_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let out_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let iteration_params_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(iteration_params),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            });
+        let bind_group = bind(&in_buffer, &out_buffer, &params_buffer);
+        Workspace {
+            n,
+            size: size as u64,
+            in_buffer,
+            out_buffer,
+            params_buffer,
+            iteration_params_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    // Stage the adjacency matrix here before running.
+    pub fn in_buffer(&self) -> &wgpu::Buffer {
+        &self.in_buffer
+    }
+
+    // Holds the distance matrix after running.
+    pub fn out_buffer(&self) -> &wgpu::Buffer {
+        &self.out_buffer
+    }
+
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub(crate) fn encode_params(&self, encoder: &mut wgpu::CommandEncoder, iteration: usize) {
+        encoder.copy_buffer_to_buffer(
+            &self.iteration_params_buffer,
+            (iteration * 8) as u64,
+            &self.params_buffer,
+            0,
+            8,
+        );
+    }
+
+    pub(crate) fn encode_feedback(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_buffer_to_buffer(&self.out_buffer, 0, &self.in_buffer, 0, self.size);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fs, process::Command};
+
+fn apsp(args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_apsp"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn parse_matrix(output: &str, separator: char) -> Vec<Vec<f32>> {
+    output
+        .lines()
+        .map(|line| line.split(separator).map(|d| d.parse().unwrap()).collect())
+        .collect()
+}
+
+#[test]
+fn test_apsp_algorithms() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("graph.txt");
+    // A path 0 - 1 - ... - 19 and an isolated vertex 20.
+    let mut content = "# path graph\n".to_string();
+    for i in 1..20 {
+        content += &format!("{} {}\n", i - 1, i);
+    }
+    content += "20 20\n";
+    fs::write(&path, content).unwrap();
+
+    for algo in ["wf", "tropical-naive", "tropical-block", "bfs", "cpu"] {
+        let (success, stdout, stderr) = apsp(&["--algo", algo, path.to_str().unwrap()]);
+        assert!(success, "{}: {}", algo, stderr);
+        let distance = parse_matrix(&stdout, '\t');
+        assert_eq!(distance.len(), 21, "{}", algo);
+        for (i, row) in distance.iter().enumerate().take(20) {
+            for (j, &d) in row.iter().enumerate().take(20) {
+                assert_eq!(d, (i as f32 - j as f32).abs(), "{}", algo);
+            }
+            assert_eq!(row[20], f32::INFINITY, "{}", algo);
+        }
+        assert_eq!(distance[20][20], 0., "{}", algo);
+    }
+}
+
+#[test]
+fn test_apsp_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("graph.mtx");
+    fs::write(
+        &input,
+        "%%MatrixMarket matrix coordinate pattern symmetric\n% cycle\n4 4 4\n2 1\n3 2\n4 3\n4 1\n",
+    )
+    .unwrap();
+    let output = dir.path().join("distance.csv");
+    let (success, _, stderr) = apsp(&[
+        "--format",
+        "csv",
+        "--timing",
+        "-o",
+        output.to_str().unwrap(),
+        input.to_str().unwrap(),
+    ]);
+    assert!(success, "{}", stderr);
+    assert!(stderr.contains("compute"));
+    let distance = parse_matrix(&fs::read_to_string(&output).unwrap(), ',');
+    assert_eq!(distance[0], vec![0., 1., 2., 1.]);
+
+    let input = dir.path().join("graph.graph");
+    fs::write(&input, "3 2\n2\n1 3\n2\n").unwrap();
+    let (success, stdout, stderr) = apsp(&["--format", "metrics", input.to_str().unwrap()]);
+    assert!(success, "{}", stderr);
+    assert!(stdout.contains("diameter\t2\n"));
+    assert!(stdout.contains("center\t1\n"));
+}
+
+#[test]
+fn test_apsp_weighted_directed() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("graph.gr");
+    fs::write(&input, "c cycle\np sp 3 3\na 1 2 5\na 2 3 2\na 3 1 1\n").unwrap();
+    let expected = vec![vec![0., 5., 7.], vec![3., 0., 2.], vec![1., 6., 0.]];
+    for algo in ["wf", "tropical-naive", "tropical-block", "cpu"] {
+        let (success, stdout, stderr) = apsp(&["--algo", algo, input.to_str().unwrap()]);
+        assert!(success, "{}: {}", algo, stderr);
+        assert_eq!(parse_matrix(&stdout, '\t'), expected, "{}", algo);
+    }
+
+    let (success, stdout, stderr) = apsp(&["--format", "ss", input.to_str().unwrap()]);
+    assert!(success, "{}", stderr);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "p res sp ss wf");
+    assert_eq!(lines[1], "g 3 3 1 5");
+    assert_eq!(lines[3..], ["d 1 12", "d 2 5", "d 3 7"]);
+
+    let (success, _, stderr) = apsp(&["--algo", "bfs", input.to_str().unwrap()]);
+    assert!(!success);
+    assert!(stderr.contains("bfs only supports"), "{}", stderr);
+}
+
+#[test]
+fn test_apsp_directed_edge_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("graph.txt");
+    fs::write(&input, "0 1 5\n1 2 2\n2 0 1\n").unwrap();
+    let (success, stdout, stderr) = apsp(&[input.to_str().unwrap()]);
+    assert!(success, "{}", stderr);
+    assert_eq!(
+        parse_matrix(&stdout, '\t'),
+        vec![vec![0., 3., 1.], vec![3., 0., 2.], vec![1., 2., 0.]]
+    );
+
+    for algo in ["wf", "tropical-block", "cpu"] {
+        let (success, stdout, stderr) =
+            apsp(&["--directed", "--algo", algo, input.to_str().unwrap()]);
+        assert!(success, "{}: {}", algo, stderr);
+        assert_eq!(
+            parse_matrix(&stdout, '\t'),
+            vec![vec![0., 5., 7.], vec![3., 0., 2.], vec![1., 6., 0.]],
+            "{}",
+            algo
+        );
+    }
+}
+
+#[test]
+fn test_apsp_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("graph.txt");
+    fs::write(&path, "0 1\n1 x\n").unwrap();
+    let (success, _, stderr) = apsp(&["--algo", "cpu", path.to_str().unwrap()]);
+    assert!(!success);
+    assert!(stderr.contains("line 2: invalid vertex"), "{}", stderr);
+
+    let (success, _, stderr) = apsp(&["--algo", "dijkstra", path.to_str().unwrap()]);
+    assert!(!success);
+    assert!(stderr.contains("unknown algorithm dijkstra"), "{}", stderr);
+}
+
+#[test]
+fn test_apsp_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("graph.txt");
+    fs::write(&input, "alice bob\nbob carol\ndave dave\n").unwrap();
+    let (success, stdout, stderr) = apsp(&[
+        "--indexing",
+        "labels",
+        "--format",
+        "csv",
+        "--infinity",
+        "",
+        input.to_str().unwrap(),
+    ]);
+    assert!(success, "{}", stderr);
+    assert_eq!(
+        stdout,
+        ",alice,bob,carol,dave\nalice,0,1,2,\nbob,1,0,1,\ncarol,2,1,0,\ndave,,,,0\n"
+    );
+
+    let output = dir.path().join("distance.npy");
+    let (success, _, stderr) = apsp(&[
+        "--indexing",
+        "labels",
+        "--format",
+        "npy",
+        "-o",
+        output.to_str().unwrap(),
+        input.to_str().unwrap(),
+    ]);
+    assert!(success, "{}", stderr);
+    let matrix = wgpu_test::io::read_npy::<f32, _>(fs::File::open(&output).unwrap()).unwrap();
+    assert_eq!(matrix.n(), 4);
+    assert_eq!(matrix.get(0, 2), 2.);
+    assert_eq!(matrix.get(3, 0), f32::INFINITY);
+}
@@ -0,0 +1,63 @@
+use std::{fs, path::Path, process::Command};
+
+fn header() -> String {
+    let mut header = Vec::new();
+    cbindgen::generate(env!("CARGO_MANIFEST_DIR"))
+        .unwrap()
+        .write(&mut header);
+    String::from_utf8(header).unwrap()
+}
+
+// Regenerate with `UPDATE_HEADER=1 cargo test --features capi --test capi`.
+#[test]
+fn test_header_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/wgpu_test.h");
+    let header = header();
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        fs::write(&path, &header).unwrap();
+    }
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        header,
+        "include/wgpu_test.h is out of date"
+    );
+}
+
+#[test]
+fn test_c_program() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // The cdylib is built next to the test binaries.
+    let exe = std::env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    let program = out_dir.path().join("capi");
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("tests/capi/test.c"))
+        .arg("-o")
+        .arg(&program)
+        .arg("-L")
+        .arg(lib_dir)
+        .arg("-lwgpu_test")
+        .arg("-lm")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // The library path set by cargo may find an older copy of the library
+    // in `target/debug` first.
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,101 @@
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "wgpu_test.h"
+
+#define N 5
+
+#define CHECK(call)                                                            \
+    do {                                                                       \
+        ApspStatus status = (call);                                            \
+        if (status != APSP_STATUS_OK) {                                        \
+            fprintf(stderr, "%s:%d: %s: %s (%s)\n", __FILE__, __LINE__,       \
+                    #call, apsp_status_string(status),                         \
+                    context ? apsp_last_error(context) : "");                  \
+            return 1;                                                          \
+        }                                                                      \
+    } while (0)
+
+#define EXPECT(cond)                                                           \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            fprintf(stderr, "%s:%d: expected %s\n", __FILE__, __LINE__,       \
+                    #cond);                                                    \
+            return 1;                                                          \
+        }                                                                      \
+    } while (0)
+
+int main(void) {
+    ApspContext *context = NULL;
+    CHECK(apsp_context_create(&context));
+
+    /* Errors before anything is staged. */
+    float distance[N * N];
+    EXPECT(apsp_run(context, APSP_ALGORITHM_WARSHALL_FLOYD) ==
+           APSP_STATUS_NOT_STAGED);
+    EXPECT(strlen(apsp_last_error(context)) > 0);
+    EXPECT(apsp_copy_distances(context, distance, N * N) ==
+           APSP_STATUS_NOT_RUN);
+
+    /* Path 0 - 1 - 2 - 3, vertex 4 is isolated. */
+    const uint32_t path[] = {0, 1, 1, 2, 2, 3};
+    const ApspAlgorithm algorithms[] = {
+        APSP_ALGORITHM_WARSHALL_FLOYD, APSP_ALGORITHM_TROPICAL_NAIVE,
+        APSP_ALGORITHM_TROPICAL_BLOCK, APSP_ALGORITHM_BFS, APSP_ALGORITHM_CPU,
+    };
+    CHECK(apsp_stage_edges(context, N, path, NULL, 3, false));
+    EXPECT(apsp_vertex_count(context) == N);
+    for (size_t a = 0; a < sizeof(algorithms) / sizeof(algorithms[0]); a++) {
+        CHECK(apsp_run(context, algorithms[a]));
+        CHECK(apsp_copy_distances(context, distance, N * N));
+        EXPECT(strlen(apsp_last_error(context)) == 0);
+        for (int i = 0; i < 4; i++) {
+            for (int j = 0; j < 4; j++) {
+                EXPECT(distance[i * N + j] == (float)abs(i - j));
+            }
+            EXPECT(isinf(distance[i * N + 4]));
+        }
+        EXPECT(distance[4 * N + 4] == 0);
+    }
+
+    /* Weighted and directed: 0 -> 1 -> 2 is shorter than 0 -> 2. */
+    const uint32_t edges[] = {0, 1, 1, 2, 0, 2};
+    const float weights[] = {1.5f, 2.0f, 4.0f};
+    CHECK(apsp_stage_edges(context, 3, edges, weights, 3, true));
+    EXPECT(apsp_run(context, APSP_ALGORITHM_BFS) ==
+           APSP_STATUS_INVALID_ARGUMENT);
+    CHECK(apsp_run(context, APSP_ALGORITHM_WARSHALL_FLOYD));
+    EXPECT(apsp_copy_distances(context, distance, 8) ==
+           APSP_STATUS_BUFFER_TOO_SMALL);
+    CHECK(apsp_copy_distances(context, distance, 9));
+    EXPECT(distance[0 * 3 + 2] == 3.5f);
+    EXPECT(isinf(distance[2 * 3 + 0]));
+
+    /* No vertices: every algorithm returns an empty matrix. */
+    CHECK(apsp_stage_edges(context, 0, NULL, NULL, 0, false));
+    for (size_t a = 0; a < sizeof(algorithms) / sizeof(algorithms[0]); a++) {
+        CHECK(apsp_run(context, algorithms[a]));
+        CHECK(apsp_copy_distances(context, distance, 0));
+    }
+
+    const uint32_t invalid[] = {0, 3};
+    EXPECT(apsp_stage_edges(context, 3, invalid, NULL, 1, false) ==
+           APSP_STATUS_INVALID_ARGUMENT);
+    const float negative[] = {1.0f, -2.0f, 4.0f};
+    EXPECT(apsp_stage_edges(context, 3, edges, negative, 3, true) ==
+           APSP_STATUS_INVALID_ARGUMENT);
+    const float nan[] = {1.0f, NAN, 4.0f};
+    EXPECT(apsp_stage_edges(context, 3, edges, nan, 3, true) ==
+           APSP_STATUS_INVALID_ARGUMENT);
+    EXPECT(apsp_run(NULL, APSP_ALGORITHM_CPU) == APSP_STATUS_INVALID_ARGUMENT);
+    EXPECT(apsp_run(context, 99) == APSP_STATUS_INVALID_ARGUMENT);
+    EXPECT(strcmp(apsp_status_string(APSP_STATUS_TOO_LARGE),
+                  "graph too large for the device") == 0);
+    EXPECT(strcmp(apsp_status_string(99), "unknown status") == 0);
+    EXPECT(strlen(apsp_last_error(context)) > 0);
+
+    apsp_context_destroy(context);
+    apsp_context_destroy(NULL);
+    return 0;
+}
@@ -0,0 +1,37 @@
+// Run with `wasm-pack test --node -- --features wasm --test wasm`. Node has no
+// `navigator.gpu`, so this covers the CPU path.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+use wgpu_test::wasm::{floyd_warshall, floyd_warshall_cpu, has_webgpu};
+
+#[wasm_bindgen_test]
+fn test_floyd_warshall_cpu() {
+    let distance = floyd_warshall_cpu(4, &[0, 1, 1, 2], None, false).unwrap();
+    let inf = f32::INFINITY;
+    assert_eq!(
+        distance,
+        vec![0., 1., 2., inf, 1., 0., 1., inf, 2., 1., 0., inf, inf, inf, inf, 0.]
+    );
+
+    let distance = floyd_warshall_cpu(3, &[0, 1, 1, 2, 0, 2], Some(vec![1., 2., 4.]), true);
+    let distance = distance.unwrap();
+    assert_eq!(distance[2], 3.);
+    assert_eq!(distance[6], inf);
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_edges() {
+    assert!(floyd_warshall_cpu(2, &[0, 1, 1], None, false).is_err());
+    assert!(floyd_warshall_cpu(2, &[0, 2], None, false).is_err());
+    assert!(floyd_warshall_cpu(2, &[0, 1], Some(vec![1., 2.]), false).is_err());
+}
+
+#[wasm_bindgen_test]
+async fn test_fallback() {
+    assert!(!has_webgpu());
+    let distance = floyd_warshall(3, vec![0, 1, 1, 2], None, false)
+        .await
+        .unwrap();
+    assert_eq!(distance, vec![0., 1., 2., 1., 0., 1., 2., 1., 0.]);
+}